@@ -1,16 +1,52 @@
 use crate::components::toast::ToastType;
-use crate::components::{create_task_modal::CreateTaskModal, toast::show_toast};
+use crate::components::{create_task_modal::CreateTaskModal, run_command_modal::RunCommandModal, toast::show_toast};
+use crate::components::log_modal::LogModal;
 use crate::components::task_list::TaskList;
 use crate::services::api;
-use models::task::{Task, NewTask};
+use models::event::TaskUpdate;
+use models::task::{Task, NewTask, QueueMove};
 use models::user::User;
+use std::rc::Rc;
 use yew::prelude::*;
 
+// 任务列表状态，直接以服务器推送的 TaskUpdate 作为 reducer 的动作
+#[derive(Default, PartialEq)]
+struct TaskStore {
+    tasks: Vec<Task>,
+}
+
+impl Reducible for TaskStore {
+    type Action = TaskUpdate;
+
+    fn reduce(self: Rc<Self>, update: TaskUpdate) -> Rc<Self> {
+        let mut tasks = self.tasks.clone();
+        match update {
+            TaskUpdate::Snapshot(snapshot) => tasks = snapshot,
+            // 创建请求的响应可能晚于推送的状态变化，已有的任务不再用它覆盖
+            TaskUpdate::Created(task) => {
+                if tasks.iter().all(|existing| existing.id != task.id) {
+                    tasks.push(task);
+                }
+            }
+            TaskUpdate::StatusChanged(task) => {
+                match tasks.iter_mut().find(|existing| existing.id == task.id) {
+                    Some(existing) => *existing = task,
+                    None => tasks.push(task),
+                }
+            }
+            TaskUpdate::Deleted(task_id) => tasks.retain(|task| task.id != task_id),
+        }
+        TaskStore { tasks }.into()
+    }
+}
+
 #[function_component(App)]
 pub fn app() -> Html {
     let current_user = use_state(|| None::<User>);
-    let tasks = use_state(Vec::<Task>::new);
+    let tasks = use_reducer(TaskStore::default);
     let show_create_modal = use_state(|| false);
+    let show_command_modal = use_state(|| false);
+    let viewing_logs = use_state(|| None::<i64>); // 正在查看日志的任务 ID
 
     let close_modal = {
         let show_create_modal = show_create_modal.clone();
@@ -26,6 +62,20 @@ pub fn app() -> Html {
         })
     };
 
+    let close_command_modal = {
+        let show_command_modal = show_command_modal.clone();
+        Callback::from(move |_| {
+            show_command_modal.set(false);
+        })
+    };
+
+    let toggle_command_modal = {
+        let show_command_modal = show_command_modal.clone();
+        Callback::from(move |_| {
+            show_command_modal.set(!*show_command_modal);
+        })
+    };
+
     let on_create_task = {
         let tasks = tasks.clone();
         Callback::from(move |new_task: NewTask| {
@@ -35,7 +85,7 @@ pub fn app() -> Html {
                 match response {
                     Ok(created_task) => {
                         // 更新任务列表
-                        tasks.set((*tasks).clone().into_iter().chain(std::iter::once(created_task)).collect::<Vec<Task>>());
+                        tasks.dispatch(TaskUpdate::Created(created_task));
                         // 可能需要关闭模态窗口或重置表单
                         // ...
                         show_toast("Create task success.", ToastType::Success);
@@ -59,7 +109,7 @@ pub fn app() -> Html {
             api::get_tasks(Callback::from(move |response| {
                 match response {
                     Ok(fetched_tasks) => {
-                        tasks.set(fetched_tasks);
+                        tasks.dispatch(TaskUpdate::Snapshot(fetched_tasks));
                         show_toast("Refresh tasks Ok.", ToastType::Success);
                     }
                     Err(err) => {
@@ -77,7 +127,8 @@ pub fn app() -> Html {
             api::delete_task(task_id, Callback::from(move |response| {
                 match response {
                     Ok(_) => {
-                        // 这里可能需要更新任务列表等
+                        // 从列表中移除已删除的任务
+                        tasks.dispatch(TaskUpdate::Deleted(task_id));
                         show_toast("Delete task success.", ToastType::Success);
                     }
                     Err(err) => {
@@ -89,13 +140,11 @@ pub fn app() -> Html {
     };
 
     let on_stop_task = {
-        let tasks = tasks.clone();
         Callback::from(move |task_id: i64| {
-            let tasks = tasks.clone();
             api::stop_task(task_id, Callback::from(move |response| {
                 match response {
                     Ok(_) => {
-                        // 可能需要将任务状态更新为已停止
+                        // 新状态会通过 /api/events 推送
                         show_toast("Stop task success.", ToastType::Success);
                     }
                     Err(err) => {
@@ -106,25 +155,57 @@ pub fn app() -> Html {
         })
     };
 
-    // 查看任务日志的回调
-    let on_view_logs = {
-        let tasks = tasks.clone();
+    let on_retry_task = {
         Callback::from(move |task_id: i64| {
-            let tasks = tasks.clone();
-            api::get_task_logs(task_id, Callback::from(move |response| {
+            api::retry_task(task_id, Callback::from(move |response| {
                 match response {
-                    Ok(logs) => {
-                        // 这里可以处理日志的显示逻辑
-                        show_toast("View logs success.", ToastType::Success);
+                    Ok(_) => {
+                        // 新状态会通过 /api/events 推送
+                        show_toast("Retry task success.", ToastType::Success);
                     }
                     Err(err) => {
-                        show_toast(&format!("Error getting task logs: {}", err), ToastType::Error);
+                        show_toast(&format!("Error retrying task: {}", err), ToastType::Error);
                     }
                 }
             }))
         })
     };
 
+    let on_move_task = {
+        Callback::from(move |(task_id, to): (i64, QueueMove)| {
+            api::move_task(task_id, to, Callback::from(move |response| {
+                // 新的队列位置会通过 /api/events 推送
+                if let Err(err) = response {
+                    show_toast(&format!("Error moving task: {}", err), ToastType::Error);
+                }
+            }))
+        })
+    };
+
+    // 查看任务日志的回调
+    let on_view_logs = {
+        let viewing_logs = viewing_logs.clone();
+        Callback::from(move |task_id: i64| {
+            viewing_logs.set(Some(task_id));
+        })
+    };
+
+    let close_logs = {
+        let viewing_logs = viewing_logs.clone();
+        Callback::from(move |_| {
+            viewing_logs.set(None);
+        })
+    };
+
+    // 订阅任务变化推送，原地更新任务列表
+    {
+        let tasks = tasks.clone();
+        use_effect_with((), move |_| {
+            let subscription = api::watch_task_updates(Callback::from(move |update| tasks.dispatch(update)));
+            move || subscription.abort()
+        });
+    }
+
     {
         let current_user = current_user.clone();
         use_effect_with(
@@ -151,6 +232,12 @@ pub fn app() -> Html {
                 >
                     { "Create Task" }
                 </button>
+                <button
+                    class="btn btn-outline btn-success btn-sm scale-90 hover:scale-100 transition-all duration-300"
+                    onclick={toggle_command_modal}
+                >
+                    { "Run Command" }
+                </button>
                 <button
                     class="btn btn-outline btn-accent btn-sm scale-90 hover:scale-100 transition-all duration-300"
                     onclick={on_refresh_tasks}
@@ -162,6 +249,16 @@ pub fn app() -> Html {
                 } else {
                     html! {} 
                 }}
+                { if *show_command_modal {
+                    html! { <RunCommandModal on_close={close_command_modal.clone()} on_create={on_create_task.clone()}/> }
+                } else {
+                    html! {}
+                }}
+                { if let Some(task_id) = *viewing_logs {
+                    html! { <LogModal task_id={task_id} on_close={close_logs.clone()}/> }
+                } else {
+                    html! {}
+                }}
                 // 显示当前用户信息
                 { if let Some(user) = (*current_user).as_ref() {
                     html! {
@@ -188,9 +285,11 @@ pub fn app() -> Html {
                     html! {}
                 }}
                 <TaskList
-                    tasks={(*tasks).clone()}
+                    tasks={tasks.tasks.clone()}
                     on_delete={on_delete_task}
                     on_stop={on_stop_task}
+                    on_retry={on_retry_task}
+                    on_move={on_move_task}
                     on_view_logs={on_view_logs}
                 />
                 // DaisyUI Toast 容器
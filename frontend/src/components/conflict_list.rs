@@ -0,0 +1,36 @@
+use crate::components::toast::{show_toast, ToastType};
+use crate::services::api;
+use models::vcs::Conflict;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ConflictListProps {
+    pub task_id: i64,
+}
+
+// 列出冲突任务中阻止合并的路径
+#[function_component(ConflictList)]
+pub fn conflict_list(props: &ConflictListProps) -> Html {
+    let conflicts = use_state(Vec::<Conflict>::new);
+
+    {
+        let conflicts = conflicts.clone();
+        use_effect_with(props.task_id, move |task_id| {
+            api::get_task_conflicts(*task_id, Callback::from(move |response| match response {
+                Ok(fetched) => conflicts.set(fetched),
+                Err(err) => show_toast(&format!("Error loading conflicts: {}", err), ToastType::Error),
+            }));
+        });
+    }
+
+    html! {
+        <ul class="text-xs mt-1">
+            { for conflicts.iter().map(|conflict| html! {
+                <li>
+                    <span class="badge badge-error badge-xs mr-1">{ &conflict.kind }</span>
+                    <span class="font-mono">{ &conflict.path }</span>
+                </li>
+            }) }
+        </ul>
+    }
+}
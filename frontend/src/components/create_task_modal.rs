@@ -1,6 +1,10 @@
+use crate::components::toast::{show_toast, ToastType};
+use crate::services::api;
 use models::task::NewTask;
+use models::revision::RevisionSet;
+use models::vcs::{Branch, Commit, MergePreview, Repository, VcsKind};
 use yew::prelude::*;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 
 #[derive(Properties, PartialEq)]
 pub struct CreateTaskModalProps {
@@ -8,30 +12,232 @@ pub struct CreateTaskModalProps {
     pub on_create: Callback<NewTask>,
 }
 
+// 逗号分隔的 Git 提交列表
+fn split_commits(revisions: &str) -> Vec<&str> {
+    revisions
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+// 在修订列表中勾选或取消某个修订：SVN 修订按规范形式合并为范围，Git 提交保持勾选顺序
+fn toggle_revision(kind: VcsKind, revisions: &str, revision: &str) -> String {
+    match kind {
+        VcsKind::Svn => {
+            let mut set = RevisionSet::parse(revisions).unwrap_or_default();
+            if let Ok(revision) = revision.parse() {
+                if set.contains(revision) {
+                    set.remove(revision);
+                } else {
+                    set.insert(revision);
+                }
+            }
+            set.to_string()
+        }
+        VcsKind::Git => {
+            let mut selected = split_commits(revisions);
+            if let Some(index) = selected.iter().position(|selected| *selected == revision) {
+                selected.remove(index);
+            } else {
+                selected.push(revision);
+            }
+            selected.join(",")
+        }
+    }
+}
+
+fn is_selected(kind: VcsKind, revisions: &str, revision: &str) -> bool {
+    match kind {
+        VcsKind::Svn => match (RevisionSet::parse(revisions), revision.parse()) {
+            (Ok(set), Ok(revision)) => set.contains(revision),
+            _ => false,
+        },
+        VcsKind::Git => split_commits(revisions).contains(&revision),
+    }
+}
+
+// 列表中显示的修订：SVN 为 r 加修订号，Git 为短哈希
+fn display_revision(kind: VcsKind, revision: &str) -> String {
+    match kind {
+        VcsKind::Svn => format!("r{}", revision),
+        VcsKind::Git => revision.chars().take(10).collect(),
+    }
+}
+
 #[function_component(CreateTaskModal)]
 pub fn create_task_modal(props: &CreateTaskModalProps) -> Html {
     let name = use_state(|| "".to_string());
+    let repositories = use_state(Vec::<Repository>::new);
+    let repository = use_state(|| "".to_string()); // 为空时使用默认版本库
     let branch = use_state(|| "".to_string());
-    let svn_merge_number = use_state(|| "".to_string());
+    let revisions = use_state(|| "".to_string());
+    let priority = use_state(|| 0_i32);
+    let depends_on = use_state(|| "".to_string());
+    let max_attempts = use_state(|| None::<u32>);
+    let backoff_secs = use_state(|| None::<u64>);
+    let timeout_secs = use_state(|| None::<u64>);
+    let run_at = use_state(|| "".to_string());
+    let branches = use_state(Vec::<Branch>::new);
+    let source = use_state(|| "trunk".to_string()); // 任务的来源分支，修订从中选择
+    let commits = use_state(Vec::<Commit>::new);
+    let preview = use_state(|| None::<MergePreview>);
+    let only_eligible = use_state(|| true); // 只列出尚未合并到目标分支的提交
+
+    // 加载版本库列表，默认选中第一个
+    {
+        let repositories = repositories.clone();
+        let repository = repository.clone();
+        use_effect_with((), move |_| {
+            api::get_repositories(Callback::from(move |response| match response {
+                Ok(fetched) => {
+                    if let Some(first) = fetched.first() {
+                        repository.set(first.name.clone());
+                    }
+                    repositories.set(fetched);
+                }
+                Err(err) => show_toast(&format!("Error loading repositories: {}", err), ToastType::Error),
+            }));
+        });
+    }
+
+    // 版本库变化时重新加载分支列表，来源分支重置为该版本库的默认来源
+    {
+        let branches = branches.clone();
+        let source = source.clone();
+        let repositories = repositories.clone();
+        use_effect_with((*repository).clone(), move |repository| {
+            if let Some(info) = repositories.iter().find(|info| info.name == *repository) {
+                source.set(info.merge_source.clone());
+            }
+            branches.set(vec![]);
+            api::get_branches(repository, false, Callback::from(move |response| match response {
+                Ok(fetched) => branches.set(fetched),
+                Err(err) => show_toast(&format!("Error loading branches: {}", err), ToastType::Error),
+            }));
+        });
+    }
+
+    // 目标分支为已知分支时才能查询可合并的提交
+    let target = if branches.iter().any(|known| known.name == *branch) {
+        (*branch).clone()
+    } else {
+        String::new()
+    };
+    let eligible_mode = *only_eligible && !target.is_empty();
+
+    // 版本库、分支或列表模式变化时重新加载提交列表
+    {
+        let commits = commits.clone();
+        let deps = ((*repository).clone(), (*source).clone(), target.clone(), eligible_mode);
+        use_effect_with(deps, move |(repository, source, target, eligible_mode)| {
+            commits.set(vec![]);
+            let callback = Callback::from(move |response| match response {
+                Ok(fetched) => commits.set(fetched),
+                Err(err) => show_toast(&format!("Error loading commits: {}", err), ToastType::Error),
+            });
+            if *eligible_mode {
+                api::get_eligible_commits(repository, target, source, callback);
+            } else {
+                api::get_commits(repository, source, None, callback);
+            }
+        });
+    }
+
+    let on_load_more = {
+        let repository = repository.clone();
+        let source = source.clone();
+        let commits = commits.clone();
+        Callback::from(move |_| {
+            let before_revision = commits.last().map(|commit| commit.revision.clone());
+            let commits = commits.clone();
+            api::get_commits(&repository, &source, before_revision.as_deref(), Callback::from(move |response| match response {
+                Ok(fetched) => commits.set((*commits).iter().cloned().chain(fetched).collect()),
+                Err(err) => show_toast(&format!("Error loading commits: {}", err), ToastType::Error),
+            }));
+        })
+    };
+
+    let on_preview = {
+        let priority = priority.clone();
+        let depends_on = depends_on.clone();
+        let max_attempts = max_attempts.clone();
+        let backoff_secs = backoff_secs.clone();
+        let timeout_secs = timeout_secs.clone();
+        let run_at = run_at.clone();
+        let name = name.clone();
+        let source = source.clone();
+        let repository = repository.clone();
+        let branch = branch.clone();
+        let revisions = revisions.clone();
+        let preview = preview.clone();
+        Callback::from(move |_| {
+            let task = NewTask {
+                creator: (*name).clone(),
+                repository: (*repository).clone(),
+                source_branch: (*source).clone(),
+                target_branch: (*branch).clone(),
+                revisions: (*revisions).clone(),
+                priority: *priority,
+                depends_on: parse_task_ids(&depends_on),
+                max_attempts: *max_attempts,
+                backoff_secs: *backoff_secs,
+                timeout_secs: *timeout_secs,
+                run_at: Some((*run_at).clone()).filter(|run_at| !run_at.is_empty()),
+                dry_run: true,
+                ..Default::default()
+            };
+            preview.set(None);
+            let preview = preview.clone();
+            api::preview_task(task, Callback::from(move |response| match response {
+                Ok(result) => preview.set(Some(result)),
+                Err(err) => show_toast(&format!("Error previewing merge: {}", err), ToastType::Error),
+            }));
+        })
+    };
 
     let on_submit = {
+        let priority = priority.clone();
+        let depends_on = depends_on.clone();
+        let max_attempts = max_attempts.clone();
+        let backoff_secs = backoff_secs.clone();
+        let timeout_secs = timeout_secs.clone();
+        let run_at = run_at.clone();
         let name = name.clone();
+        let source = source.clone();
+        let repository = repository.clone();
         let branch = branch.clone();
-        let svn_merge_number = svn_merge_number.clone();
+        let revisions = revisions.clone();
         let on_close = props.on_close.clone();
         let on_create = props.on_create.clone();
         Callback::from(move |_| {
             let task = NewTask {
                 creator: (*name).clone(),
-                branch: (*branch).clone(),
-                svn_merge_number: (*svn_merge_number).clone(),
+                repository: (*repository).clone(),
+                source_branch: (*source).clone(),
+                target_branch: (*branch).clone(),
+                revisions: (*revisions).clone(),
+                priority: *priority,
+                depends_on: parse_task_ids(&depends_on),
+                max_attempts: *max_attempts,
+                backoff_secs: *backoff_secs,
+                timeout_secs: *timeout_secs,
+                run_at: Some((*run_at).clone()).filter(|run_at| !run_at.is_empty()),
+                dry_run: false,
+                ..Default::default()
             };
             // 发送任务创建请求
             on_create.emit(task);
             // 清空表单字段并关闭模态框
             name.set("".to_string());
             branch.set("".to_string());
-            svn_merge_number.set("".to_string());
+            revisions.set("".to_string());
+            priority.set(0);
+            depends_on.set("".to_string());
+            max_attempts.set(None);
+            backoff_secs.set(None);
+            timeout_secs.set(None);
+            run_at.set("".to_string());
             on_close.emit(());
         })
     };
@@ -43,9 +249,15 @@ pub fn create_task_modal(props: &CreateTaskModalProps) -> Html {
         })
     };
 
+    let kind = repositories
+        .iter()
+        .find(|info| info.name == *repository)
+        .map(|info| info.kind)
+        .unwrap_or_default();
+
     html! {
         <div class="modal modal-open">
-            <div class="modal-box">
+            <div class="modal-box w-11/12 max-w-3xl">
                 <h3 class="font-bold text-lg">{ "Create New Task" }</h3>
                 <input
                     type="text"
@@ -57,9 +269,24 @@ pub fn create_task_modal(props: &CreateTaskModalProps) -> Html {
                         name.set(input.value());
                     })}
                 />
+                <select
+                    class="select select-bordered w-full my-2"
+                    onchange={
+                        let repository = repository.clone();
+                        Callback::from(move |e: Event| {
+                            let select = e.target_unchecked_into::<HtmlSelectElement>();
+                            repository.set(select.value());
+                        })
+                    }
+                >
+                    { for repositories.iter().map(|info| html! {
+                        <option value={info.name.clone()} selected={info.name == *repository}>{ &info.name }</option>
+                    }) }
+                </select>
                 <input
                     type="text"
-                    placeholder="Branch Name"
+                    placeholder="Target Branch"
+                    list="branch-options"
                     class="input input-bordered w-full my-2"
                     value={(*branch).clone()}
                     oninput={Callback::from(move |e: InputEvent| {
@@ -67,17 +294,206 @@ pub fn create_task_modal(props: &CreateTaskModalProps) -> Html {
                         branch.set(input.value());
                     })}
                 />
+                <datalist id="branch-options">
+                    { for branches.iter().map(|branch| html! { <option value={branch.name.clone()} /> }) }
+                </datalist>
                 <input
                     type="text"
-                    placeholder="SVN Merge Number"
+                    placeholder="Revisions (e.g. 1234,1240 or 1200-1250)"
                     class="input input-bordered w-full my-2"
-                    value={(*svn_merge_number).clone()}
-                    oninput={Callback::from(move |e: InputEvent| {
-                        let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
-                        svn_merge_number.set(input.value());
-                    })}
+                    value={(*revisions).clone()}
+                    oninput={
+                        let revisions = revisions.clone();
+                        Callback::from(move |e: InputEvent| {
+                            let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                            revisions.set(input.value());
+                        })
+                    }
                 />
+                <label class="flex items-center space-x-2 my-2">
+                    <span class="text-sm">{ "Priority" }</span>
+                    <input
+                        type="number"
+                        class="input input-bordered input-sm w-24"
+                        value={priority.to_string()}
+                        oninput={
+                            let priority = priority.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                                priority.set(input.value().parse().unwrap_or_default());
+                            })
+                        }
+                    />
+                    <span class="text-sm">{ "Max attempts" }</span>
+                    <input
+                        type="number"
+                        min="1"
+                        placeholder="default"
+                        class="input input-bordered input-sm w-24"
+                        value={max_attempts.map(|attempts| attempts.to_string()).unwrap_or_default()}
+                        oninput={
+                            let max_attempts = max_attempts.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                                // 留空时使用服务端的重试配置
+                                max_attempts.set(input.value().parse().ok().filter(|attempts| *attempts > 0));
+                            })
+                        }
+                    />
+                    <span class="text-sm">{ "Backoff (s)" }</span>
+                    <input
+                        type="number"
+                        min="0"
+                        placeholder="default"
+                        class="input input-bordered input-sm w-24"
+                        value={backoff_secs.map(|secs| secs.to_string()).unwrap_or_default()}
+                        oninput={
+                            let backoff_secs = backoff_secs.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                                // 留空时使用服务端的重试配置
+                                backoff_secs.set(input.value().parse().ok());
+                            })
+                        }
+                    />
+                    <span class="text-sm">{ "Timeout (s)" }</span>
+                    <input
+                        type="number"
+                        min="0"
+                        placeholder="default"
+                        class="input input-bordered input-sm w-24"
+                        value={timeout_secs.map(|secs| secs.to_string()).unwrap_or_default()}
+                        oninput={
+                            let timeout_secs = timeout_secs.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                                // 留空时使用服务端的超时配置，0 表示不限制
+                                timeout_secs.set(input.value().parse().ok());
+                            })
+                        }
+                    />
+                </label>
+                <label class="flex items-center space-x-2 my-2">
+                    <span class="text-sm">{ "Run at" }</span>
+                    // datetime-local 不带时区，服务端按本地时间理解
+                    <input
+                        type="datetime-local"
+                        class="input input-bordered input-sm"
+                        value={(*run_at).clone()}
+                        oninput={
+                            let run_at = run_at.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                                run_at.set(input.value());
+                            })
+                        }
+                    />
+                </label>
+                <input
+                    type="text"
+                    placeholder="Run after tasks (e.g. 12,15)"
+                    class="input input-bordered w-full my-2"
+                    value={(*depends_on).clone()}
+                    oninput={
+                        let depends_on = depends_on.clone();
+                        Callback::from(move |e: InputEvent| {
+                            let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                            depends_on.set(input.value());
+                        })
+                    }
+                />
+                <div class="flex items-center space-x-2 my-2">
+                    <span class="text-sm">{ "Merge from" }</span>
+                    <select
+                        class="select select-bordered select-sm"
+                        onchange={
+                            let source = source.clone();
+                            Callback::from(move |e: Event| {
+                                let select = e.target_unchecked_into::<HtmlSelectElement>();
+                                source.set(select.value());
+                            })
+                        }
+                    >
+                        { for branches.iter().map(|branch| html! {
+                            <option value={branch.name.clone()} selected={branch.name == *source}>{ &branch.name }</option>
+                        }) }
+                    </select>
+                    <label class="label cursor-pointer space-x-1">
+                        <input
+                            type="checkbox"
+                            class="checkbox checkbox-xs"
+                            checked={*only_eligible}
+                            onchange={
+                                let only_eligible = only_eligible.clone();
+                                Callback::from(move |_: Event| only_eligible.set(!*only_eligible))
+                            }
+                        />
+                        <span class="text-sm">{ "Only not yet merged" }</span>
+                    </label>
+                </div>
+                <div class="h-64 overflow-auto border border-base-300 rounded">
+                    <table class="table table-xs">
+                        <tbody>
+                            {
+                                for commits.iter().map(|commit| {
+                                    let revision = commit.revision.clone();
+                                    let onchange = {
+                                        let revisions = revisions.clone();
+                                        let revision = revision.clone();
+                                        Callback::from(move |_: Event| {
+                                            revisions.set(toggle_revision(kind, &revisions, &revision));
+                                        })
+                                    };
+                                    html! {
+                                        <tr key={revision.clone()}>
+                                            <td>
+                                                <input
+                                                    type="checkbox"
+                                                    class="checkbox checkbox-xs"
+                                                    checked={is_selected(kind, &revisions, &revision)}
+                                                    {onchange}
+                                                />
+                                            </td>
+                                            <td>{ display_revision(kind, &revision) }</td>
+                                            <td>{ &commit.author }</td>
+                                            <td>{ commit.date.get(..10).unwrap_or(commit.date.as_str()) }</td>
+                                            <td class="truncate max-w-xs">{ commit.message.lines().next().unwrap_or_default() }</td>
+                                        </tr>
+                                    }
+                                })
+                            }
+                        </tbody>
+                    </table>
+                    {
+                        // 可合并的提交一次全部返回，无需翻页
+                        if eligible_mode {
+                            html! {}
+                        } else {
+                            html! { <button class="btn btn-ghost btn-xs w-full" onclick={on_load_more}>{ "Load more" }</button> }
+                        }
+                    }
+                </div>
+                {
+                    match (*preview).as_ref() {
+                        Some(preview) => html! {
+                            <div class="my-2 text-sm">
+                                <div class="font-semibold">
+                                    { format!("Preview of {}: {} paths, {} conflicts", preview.revisions, preview.paths.len(), preview.conflicts.len()) }
+                                </div>
+                                <pre class="bg-base-200 text-xs p-2 my-1 max-h-32 overflow-auto">{ &preview.commit_message }</pre>
+                                <ul class="h-32 overflow-auto font-mono text-xs">
+                                    { for preview.paths.iter().map(|path| html! { <li>{ format!("{} {}", path.action, path.path) }</li> }) }
+                                    { for preview.conflicts.iter().map(|conflict| html! {
+                                        <li class="text-error">{ format!("conflict ({}): {}", conflict.kind, conflict.path) }</li>
+                                    }) }
+                                </ul>
+                            </div>
+                        },
+                        None => html! {},
+                    }
+                }
                 <div class="modal-action">
+                    <button class="btn" onclick={on_preview}>{ "Preview" }</button>
                     <button class="btn btn-primary" onclick={on_submit}>{ "Add Task" }</button>
                     <button class="btn btn-ghost" onclick={on_close}>{ "Close" }</button>
                 </div>
@@ -85,3 +501,10 @@ pub fn create_task_modal(props: &CreateTaskModalProps) -> Html {
         </div>
     }
 }
+
+// 解析以逗号分隔的任务 ID，忽略无法识别的部分
+fn parse_task_ids(text: &str) -> Vec<i64> {
+    text.split(',')
+        .filter_map(|id| id.trim().trim_start_matches('#').parse().ok())
+        .collect()
+}
@@ -0,0 +1,108 @@
+use crate::services::api;
+use models::log::TaskLog;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct LogModalProps {
+    pub task_id: i64,
+    pub on_close: Callback<()>,
+}
+
+#[function_component(LogModal)]
+pub fn log_modal(props: &LogModalProps) -> Html {
+    let logs = use_reducer(LogLines::default);
+    let status = use_state(|| None::<String>);
+
+    {
+        let logs = logs.clone();
+        let status = status.clone();
+        use_effect_with(props.task_id, move |task_id| {
+            logs.dispatch(LogAction::Clear);
+            status.set(None);
+
+            let on_log = {
+                let logs = logs.clone();
+                Callback::from(move |log: TaskLog| logs.dispatch(LogAction::Append(log)))
+            };
+            let on_status = Callback::from(move |final_status: String| status.set(Some(final_status)));
+
+            // 组件卸载或切换任务时取消订阅以关闭连接
+            let subscription = api::stream_task_logs(*task_id, on_log, on_status);
+            move || subscription.abort()
+        });
+    }
+
+    let on_close = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_| on_close.emit(()))
+    };
+
+    html! {
+        <div class="modal modal-open">
+            <div class="modal-box w-11/12 max-w-5xl">
+                <h3 class="font-bold text-lg">
+                    { format!("Task {} Logs", props.task_id) }
+                    {
+                        match (*status).as_ref() {
+                            Some(final_status) => html! { <span class="badge badge-info ml-2">{ final_status }</span> },
+                            None => html! { <span class="loading loading-dots loading-sm ml-2"></span> },
+                        }
+                    }
+                </h3>
+                <pre class="bg-base-200 text-xs p-2 my-2 h-96 overflow-auto">
+                    {
+                        for logs.lines.iter().enumerate().map(|(index, log)| {
+                            let class = match log.stream.as_str() {
+                                "stderr" => "text-error",
+                                "system" => "text-info",
+                                _ => "",
+                            };
+                            // 每次执行的日志前加分隔行
+                            let new_attempt = log.attempt > 0
+                                && (index == 0 || logs.lines[index - 1].attempt != log.attempt);
+                            html! {
+                                <div key={log.seq}>
+                                    {
+                                        if new_attempt {
+                                            html! { <div class="font-bold border-t border-base-300 mt-1">{ format!("Attempt {}", log.attempt) }</div> }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                    <div class={class}>{ format!("{} {}", log.timestamp, log.line) }</div>
+                                </div>
+                            }
+                        })
+                    }
+                </pre>
+                <div class="modal-action">
+                    <button class="btn btn-ghost" onclick={on_close}>{ "Close" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+// 日志行列表，使用 reducer 以便在异步回调中追加
+#[derive(Default, PartialEq)]
+struct LogLines {
+    lines: Vec<TaskLog>,
+}
+
+enum LogAction {
+    Append(TaskLog),
+    Clear,
+}
+
+impl Reducible for LogLines {
+    type Action = LogAction;
+
+    fn reduce(self: std::rc::Rc<Self>, action: Self::Action) -> std::rc::Rc<Self> {
+        let mut lines = self.lines.clone();
+        match action {
+            LogAction::Append(log) => lines.push(log),
+            LogAction::Clear => lines.clear(),
+        }
+        LogLines { lines }.into()
+    }
+}
@@ -1,4 +1,7 @@
 pub mod task_list;
 pub mod create_task_modal;
+pub mod run_command_modal;
 pub mod app;
-pub mod toast;
\ No newline at end of file
+pub mod log_modal;
+pub mod toast;
+pub mod conflict_list;
\ No newline at end of file
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use crate::components::toast::{show_toast, ToastType};
+use crate::services::api;
+use models::command::CommandInfo;
+use models::task::{NewTask, TaskKind};
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+#[derive(Properties, PartialEq)]
+pub struct RunCommandModalProps {
+    pub on_close: Callback<()>,
+    pub on_create: Callback<NewTask>,
+}
+
+#[function_component(RunCommandModal)]
+pub fn run_command_modal(props: &RunCommandModalProps) -> Html {
+    let name = use_state(|| "".to_string());
+    let commands = use_state(Vec::<CommandInfo>::new);
+    let command = use_state(|| "".to_string());
+    let parameters = use_state(BTreeMap::<String, String>::new);
+
+    // 加载脚本列表，默认选中第一个
+    {
+        let commands = commands.clone();
+        let command = command.clone();
+        use_effect_with((), move |_| {
+            api::get_commands(Callback::from(move |response| match response {
+                Ok(fetched) => {
+                    if let Some(first) = fetched.first() {
+                        command.set(first.name.clone());
+                    }
+                    commands.set(fetched);
+                }
+                Err(err) => show_toast(&format!("Error loading commands: {}", err), ToastType::Error),
+            }));
+        });
+    }
+
+    // 切换脚本时清空已填写的参数
+    {
+        let parameters = parameters.clone();
+        use_effect_with((*command).clone(), move |_| {
+            parameters.set(BTreeMap::new());
+        });
+    }
+
+    let selected = commands.iter().find(|info| info.name == *command).cloned();
+
+    let on_submit = {
+        let name = name.clone();
+        let command = command.clone();
+        let parameters = parameters.clone();
+        let on_create = props.on_create.clone();
+        let on_close = props.on_close.clone();
+        Callback::from(move |_| {
+            // 未填写的参数不传，由服务端替换为空
+            let task = NewTask {
+                creator: (*name).clone(),
+                kind: TaskKind::Command,
+                command: (*command).clone(),
+                parameters: parameters
+                    .iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+                ..Default::default()
+            };
+            on_create.emit(task);
+            parameters.set(BTreeMap::new());
+            on_close.emit(());
+        })
+    };
+
+    let on_close = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_| {
+            on_close.emit(());
+        })
+    };
+
+    html! {
+        <div class="modal modal-open">
+            <div class="modal-box w-11/12 max-w-xl">
+                <h3 class="font-bold text-lg">{ "Run Command" }</h3>
+                <input
+                    type="text"
+                    placeholder="Task Name"
+                    class="input input-bordered w-full my-2"
+                    value={(*name).clone()}
+                    oninput={Callback::from(move |e: InputEvent| {
+                        let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                        name.set(input.value());
+                    })}
+                />
+                <select
+                    class="select select-bordered w-full my-2"
+                    onchange={
+                        let command = command.clone();
+                        Callback::from(move |e: Event| {
+                            let select = e.target_unchecked_into::<HtmlSelectElement>();
+                            command.set(select.value());
+                        })
+                    }
+                >
+                    { for commands.iter().map(|info| html! {
+                        <option value={info.name.clone()} selected={info.name == *command}>{ &info.name }</option>
+                    }) }
+                </select>
+                if let Some(info) = selected {
+                    if !info.description.is_empty() {
+                        <p class="text-sm opacity-70 my-2">{ &info.description }</p>
+                    }
+                    { for info.parameters.iter().map(|parameter| {
+                        let parameters = parameters.clone();
+                        let key = parameter.clone();
+                        html! {
+                            <input
+                                type="text"
+                                placeholder={parameter.clone()}
+                                class="input input-bordered w-full my-2"
+                                value={parameters.get(parameter).cloned().unwrap_or_default()}
+                                oninput={Callback::from(move |e: InputEvent| {
+                                    let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+                                    let mut updated = (*parameters).clone();
+                                    updated.insert(key.clone(), input.value());
+                                    parameters.set(updated);
+                                })}
+                            />
+                        }
+                    }) }
+                }
+                <div class="modal-action">
+                    <button class="btn btn-primary" onclick={on_submit} disabled={command.is_empty()}>{ "Run" }</button>
+                    <button class="btn btn-ghost" onclick={on_close}>{ "Close" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,11 +1,16 @@
+use crate::components::conflict_list::ConflictList;
 use yew::prelude::*;
-use models::task::Task;
+use std::collections::BTreeMap;
+
+use models::task::{QueueMove, Task, TaskKind, TaskStatus};
 
 #[derive(Properties, PartialEq)]
 pub struct TaskListProps {
     pub tasks: Vec<Task>,
     pub on_delete: Callback<i64>,
     pub on_stop: Callback<i64>,
+    pub on_retry: Callback<i64>,
+    pub on_move: Callback<(i64, QueueMove)>,
     pub on_view_logs: Callback<i64>,
 }
 
@@ -17,10 +22,14 @@ pub fn task_list(props: &TaskListProps) -> Html {
                 <thead>
                     <tr>
                         <th>{ "ID" }</th>
+                        <th>{ "Queue" }</th>
+                        <th>{ "Priority" }</th>
                         <th>{ "Created At" }</th>
                         <th>{ "Creator" }</th>
-                        <th>{ "Branch" }</th>
-                        <th>{ "SVN Merge Number" }</th>
+                        <th>{ "Repository" }</th>
+                        <th>{ "Source" }</th>
+                        <th>{ "Target" }</th>
+                        <th>{ "Revisions" }</th>
                         <th>{ "Status" }</th>
                         <th>{ "Actions" }</th>
                     </tr>
@@ -38,6 +47,21 @@ pub fn task_list(props: &TaskListProps) -> Html {
                                 let task_id = task.id;
                                 Callback::from(move |_| on_stop.emit(task_id))
                             };
+                            let on_retry = {
+                                let on_retry = props.on_retry.clone();
+                                let task_id = task.id;
+                                Callback::from(move |_| on_retry.emit(task_id))
+                            };
+                            let on_move_front = {
+                                let on_move = props.on_move.clone();
+                                let task_id = task.id;
+                                Callback::from(move |_| on_move.emit((task_id, QueueMove::Front)))
+                            };
+                            let on_move_back = {
+                                let on_move = props.on_move.clone();
+                                let task_id = task.id;
+                                Callback::from(move |_| on_move.emit((task_id, QueueMove::Back)))
+                            };
                             let on_view_logs = {
                                 let on_view_logs = props.on_view_logs.clone();
                                 let task_id = task.id;
@@ -46,26 +70,102 @@ pub fn task_list(props: &TaskListProps) -> Html {
                             html! {
                                 <tr key={task.id}>
                                     <td>{ task.id }</td>
+                                    <td>{ task.queue_position.map(|position| position.to_string()).unwrap_or_default() }</td>
+                                    <td>{ task.priority }</td>
                                     <td>{ &task.created_at }</td>
                                     <td>{ &task.creator }</td>
-                                    <td>{ &task.branch }</td>
-                                    <td>{ &task.svn_merge_number }</td>
-                                    <td>{ &task.status }</td>
+                                    <td>{ &task.repository }</td>
+                                    if task.kind == TaskKind::Command {
+                                        <td></td>
+                                        <td></td>
+                                        <td>{ command_line(task) }</td>
+                                    } else {
+                                        <td>{ if task.source_branch.is_empty() { "(default)" } else { task.source_branch.as_str() } }</td>
+                                        <td>{ &task.target_branch }</td>
+                                        <td>{ &task.revisions }</td>
+                                    }
+                                    <td>
+                                        { task.status.to_string() }
+                                        {
+                                            if task.attempt > 1 {
+                                                html! { <span class="text-xs opacity-60">{ format!(" (attempt {})", task.attempt) }</span> }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                        {
+                                            match (&task.run_at, task.status) {
+                                                (Some(run_at), TaskStatus::Scheduled) => html! { <div class="text-xs opacity-60">{ format!("at {} UTC", run_at) }</div> },
+                                                _ => html! {},
+                                            }
+                                        }
+                                        {
+                                            match task.elapsed_secs {
+                                                Some(secs) => html! { <div class="text-xs opacity-60">{ format!("{}s", secs) }</div> },
+                                                None => html! {},
+                                            }
+                                        }
+                                        {
+                                            if task.status == TaskStatus::Conflicted {
+                                                html! { <ConflictList task_id={task.id} /> }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                        {
+                                            if task.depends_on.is_empty() {
+                                                html! {}
+                                            } else {
+                                                let after = task.dependencies().iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(", ");
+                                                html! { <div class="text-xs opacity-60">{ format!("after {}", after) }</div> }
+                                            }
+                                        }
+                                    </td>
                                     <td class="flex items-center space-x-2">
                                         <button class="btn btn-ghost btn-xs btn-outline btn-info" onclick={on_view_logs}>{ "Logs" }</button>
                                         {
-                                            if task.status == "Running" {
+                                            if task.queue_position.is_some() {
+                                                html! {
+                                                    <>
+                                                        <button class="btn btn-ghost btn-xs btn-outline" title="Move to front" onclick={on_move_front}>{ "↑" }</button>
+                                                        <button class="btn btn-ghost btn-xs btn-outline" title="Move to back" onclick={on_move_back}>{ "↓" }</button>
+                                                    </>
+                                                }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                        {
+                                            if !task.status.is_terminal() {
                                                 html! {
                                                     <button class="btn btn-ghost btn-outline btn-warning" onclick={on_stop}>
-                                                        { "Stop" }
+                                                        { if task.status == TaskStatus::Running { "Stop" } else { "Cancel" } }
+                                                    </button>
+                                                }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                        {
+                                            if task.status.can_retry() {
+                                                html! {
+                                                    <button class="btn btn-ghost btn-xs btn-outline btn-success" onclick={on_retry}>
+                                                        { "Retry" }
                                                     </button>
                                                 }
                                             } else {
+                                                html! {}
+                                            }
+                                        }
+                                        {
+                                            if task.status.can_delete() {
                                                 html! {
                                                     <button class="btn btn-ghost btn-xs btn-outline btn-error" onclick={on_delete}>
                                                         { "Delete" }
                                                     </button>
                                                 }
+                                            } else {
+                                                html! {}
                                             }
                                         }
                                     </td>
@@ -78,3 +178,12 @@ pub fn task_list(props: &TaskListProps) -> Html {
         </div>
     }
 }
+
+// 命令任务显示脚本名称和参数，如 deploy env=staging
+fn command_line(task: &Task) -> String {
+    let parameters: BTreeMap<String, String> = serde_json::from_str(&task.parameters).unwrap_or_default();
+    std::iter::once(task.command.clone())
+        .chain(parameters.iter().map(|(name, value)| format!("{}={}", name, value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,12 +1,15 @@
 // src/api.rs
 
+use gloo_net::eventsource::futures::EventSource;
 use gloo_net::http::{Request, Response};
-use models::{task::{NewTask, Task}, user::User};
+use models::{command::CommandInfo, event::TaskUpdate, log::TaskLog, task::{NewTask, QueueMove, Task}, user::User, vcs::{Branch, Commit, Conflict, MergePreview, Repository}};
 use serde::Deserialize;
 use wasm_bindgen_futures::spawn_local;
 use yew::Callback;
 use anyhow::Result;
-use futures::future::LocalBoxFuture;
+use futures::future::{AbortHandle, Abortable, LocalBoxFuture};
+use futures::StreamExt;
+use gloo_timers::future::TimeoutFuture;
 
 // 获取所有任务
 pub fn get_tasks(callback: Callback<Result<Vec<Task>>>) {
@@ -56,6 +59,39 @@ pub fn stop_task(task_id: i64, callback: Callback<Result<()>>) {
     });
 }
 
+// 手动重试未成功结束的任务
+pub fn retry_task(task_id: i64, callback: Callback<Result<()>>) {
+    spawn_local(async move {
+        let response = Request::post(&format!("/api/tasks/{}/retry", task_id))
+            .send()
+            .await;
+
+        handle_response(response, callback).await;
+    });
+}
+
+// 试合并，返回会改动的路径和预计的冲突，不创建任务
+pub fn preview_task(new_task: NewTask, callback: Callback<Result<MergePreview>>) {
+    let post_request = Request::post("/api/tasks/preview")
+        .json(&new_task).unwrap()
+        .send();
+
+    spawn_local(async move {
+        handle_response(post_request.await, callback).await;
+    });
+}
+
+// 调整排队中任务的位置
+pub fn move_task(task_id: i64, to: QueueMove, callback: Callback<Result<()>>) {
+    let post_request = Request::post(&format!("/api/tasks/{}/move", task_id))
+        .json(&to).unwrap()
+        .send();
+
+    spawn_local(async move {
+        handle_response(post_request.await, callback).await;
+    });
+}
+
 // 删除任务
 pub fn delete_task(task_id: i64, callback: Callback<Result<()>>) {
     spawn_local(async move {
@@ -67,10 +103,14 @@ pub fn delete_task(task_id: i64, callback: Callback<Result<()>>) {
     });
 }
 
-// 获取任务日志
-pub fn get_task_logs(task_id: i64, callback: Callback<Result<String>>) {
+// 获取任务日志，since_seq 用于增量获取
+pub fn get_task_logs(task_id: i64, since_seq: Option<i64>, callback: Callback<Result<Vec<TaskLog>>>) {
     spawn_local(async move {
-        let response = Request::get(&format!("/api/tasks/{}/logs", task_id))
+        let url = match since_seq {
+            Some(seq) => format!("/api/tasks/{}/logs?since_seq={}", task_id, seq),
+            None => format!("/api/tasks/{}/logs", task_id),
+        };
+        let response = Request::get(&url)
             .send()
             .await;
 
@@ -78,10 +118,124 @@ pub fn get_task_logs(task_id: i64, callback: Callback<Result<String>>) {
     });
 }
 
-// 获取所有分支
-pub fn get_branches(callback: Callback<Result<Vec<String>>>) {
+// 获取冲突任务的冲突路径
+pub fn get_task_conflicts(task_id: i64, callback: Callback<Result<Vec<Conflict>>>) {
+    spawn_local(async move {
+        let response = Request::get(&format!("/api/tasks/{}/conflicts", task_id))
+            .send()
+            .await;
+
+        handle_response(response, callback).await;
+    });
+}
+
+// 订阅任务日志的实时推送，on_status 在任务结束时收到最终状态，之后关闭连接
+// 连接断开后自动重连，服务器重新发送的日志按序号跳过
+// 调用返回的 AbortHandle::abort 以取消订阅
+pub fn stream_task_logs(task_id: i64, on_log: Callback<TaskLog>, on_status: Callback<String>) -> AbortHandle {
+    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+    let url = format!("/api/tasks/{}/logs/stream", task_id);
+    let stream = async move {
+        let mut last_seq = None::<i64>;
+        loop {
+            match EventSource::new(&url) {
+                Ok(mut event_source) => {
+                    if let (Ok(logs), Ok(status)) = (event_source.subscribe("log"), event_source.subscribe("status")) {
+                        let mut events = futures::stream::select(logs, status);
+                        // 连接被关闭时收到错误，稍后重连
+                        while let Some(Ok((event_type, message))) = events.next().await {
+                            let data = message.data().as_string().unwrap_or_default();
+                            if event_type == "status" {
+                                on_status.emit(data);
+                                // 任务已结束，关闭连接以免浏览器重连后重新发送全部日志
+                                event_source.close();
+                                return;
+                            }
+                            match serde_json::from_str::<TaskLog>(&data) {
+                                Ok(log) if last_seq.is_some_and(|seq| log.seq <= seq) => {}
+                                Ok(log) => {
+                                    last_seq = Some(log.seq);
+                                    on_log.emit(log);
+                                }
+                                Err(e) => gloo_console::error!(format!("Invalid log event: {}", e)),
+                            }
+                        }
+                    }
+                }
+                Err(e) => gloo_console::error!(format!("Could not connect to {}: {:?}", url, e)),
+            }
+            TimeoutFuture::new(3_000).await;
+        }
+    };
+    spawn_local(async move {
+        let _ = Abortable::new(stream, abort_registration).await;
+    });
+    abort_handle
+}
+
+// 订阅任务变化推送，连接断开后自动重连
+// 调用返回的 AbortHandle::abort 以取消订阅
+pub fn watch_task_updates(on_update: Callback<TaskUpdate>) -> AbortHandle {
+    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+    let watch = async move {
+        loop {
+            match EventSource::new("/api/events") {
+                Ok(mut event_source) => {
+                    if let Ok(mut updates) = event_source.subscribe("task") {
+                        while let Some(Ok((_, message))) = updates.next().await {
+                            let data = message.data().as_string().unwrap_or_default();
+                            match serde_json::from_str::<TaskUpdate>(&data) {
+                                Ok(update) => on_update.emit(update),
+                                Err(e) => gloo_console::error!(format!("Invalid task event: {}", e)),
+                            }
+                        }
+                    }
+                }
+                Err(e) => gloo_console::error!(format!("Could not connect to /api/events: {:?}", e)),
+            }
+            // 重连时服务器会重新发送完整列表
+            TimeoutFuture::new(3_000).await;
+        }
+    };
+    spawn_local(async move {
+        let _ = Abortable::new(watch, abort_registration).await;
+    });
+    abort_handle
+}
+
+// 获取所有已配置的版本库，第一个为默认版本库
+pub fn get_repositories(callback: Callback<Result<Vec<Repository>>>) {
+    spawn_local(async move {
+        let response = Request::get("/api/repositories")
+            .send()
+            .await;
+
+        handle_response(response, callback).await;
+    });
+}
+
+// 获取服务端配置的脚本
+pub fn get_commands(callback: Callback<Result<Vec<CommandInfo>>>) {
+    spawn_local(async move {
+        let response = Request::get("/api/commands")
+            .send()
+            .await;
+
+        handle_response(response, callback).await;
+    });
+}
+
+// 分支名用作路径中的一段，其中的 / 等字符需要编码
+fn path_segment(value: &str) -> String {
+    js_sys::encode_uri_component(value).into()
+}
+
+// 获取版本库的所有分支，refresh 为 true 时服务器忽略缓存
+pub fn get_branches(repository: &str, refresh: bool, callback: Callback<Result<Vec<Branch>>>) {
+    let query = [("repository", repository.to_string()), ("refresh", refresh.to_string())];
     spawn_local(async move {
         let response = Request::get("/api/branches")
+            .query(query)
             .send()
             .await;
 
@@ -89,16 +243,36 @@ pub fn get_branches(callback: Callback<Result<Vec<String>>>) {
     });
 }
 
-// 获取分支下的提交
-// pub fn get_commits(branch_name: &str, callback: Callback<Result<Vec<String>>>) {
-//     spawn_local(async move {
-//         let response = Request::get(&format!("/api/branches/{}/commits", branch_name))
-//             .send()
-//             .await;
+// 获取分支下的提交，before_revision 用于加载更早的一页
+pub fn get_commits(repository: &str, branch_name: &str, before_revision: Option<&str>, callback: Callback<Result<Vec<Commit>>>) {
+    let url = format!("/api/branches/{}/commits", path_segment(branch_name));
+    let mut query = vec![("repository", repository.to_string())];
+    if let Some(revision) = before_revision {
+        query.push(("before_revision", revision.to_string()));
+    }
+    spawn_local(async move {
+        let response = Request::get(&url)
+            .query(query)
+            .send()
+            .await;
 
-//         handle_response(response, callback).await;
-//     });
-// }
+        handle_response(response, callback).await;
+    });
+}
+
+// 获取来源分支上尚未合并到目标分支的提交
+pub fn get_eligible_commits(repository: &str, target: &str, source: &str, callback: Callback<Result<Vec<Commit>>>) {
+    let url = format!("/api/branches/{}/eligible", path_segment(target));
+    let query = [("repository", repository.to_string()), ("from", source.to_string())];
+    spawn_local(async move {
+        let response = Request::get(&url)
+            .query(query)
+            .send()
+            .await;
+
+        handle_response(response, callback).await;
+    });
+}
 
 pub fn get_current_user(callback: Callback<Result<User, anyhow::Error>>) {
     spawn_local(async move {
@@ -1,5 +1,4 @@
--- Run this SQL script to create the tasks table in your SQLite database (task.db)
-
+-- 初始表结构，兼容此前按 db.txt 手动创建的数据库
 CREATE TABLE IF NOT EXISTS tasks (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     created_at TEXT NOT NULL,
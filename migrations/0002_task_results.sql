@@ -0,0 +1,3 @@
+-- 记录任务执行结果
+ALTER TABLE tasks ADD COLUMN exit_code INTEGER;
+ALTER TABLE tasks ADD COLUMN finished_at TEXT;
@@ -0,0 +1,9 @@
+-- 任务执行日志，每行输出一条记录
+CREATE TABLE IF NOT EXISTS task_logs (
+    task_id INTEGER NOT NULL,
+    seq INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    stream TEXT NOT NULL,
+    line TEXT NOT NULL,
+    PRIMARY KEY (task_id, seq)
+);
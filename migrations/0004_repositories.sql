@@ -0,0 +1,3 @@
+-- 支持多个版本库：任务记录所属版本库，修订列表不再限定为 SVN
+ALTER TABLE tasks ADD COLUMN repository TEXT NOT NULL DEFAULT '';
+ALTER TABLE tasks RENAME COLUMN svn_merge_number TO revisions;
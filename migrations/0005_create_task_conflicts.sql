@@ -0,0 +1,7 @@
+-- 合并冲突时记录每个冲突路径及冲突类型
+CREATE TABLE IF NOT EXISTS task_conflicts (
+    task_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    PRIMARY KEY (task_id, path, kind)
+);
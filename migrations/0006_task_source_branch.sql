@@ -0,0 +1,4 @@
+-- 任务同时记录来源分支与目标分支，原有的 branch 即目标分支
+-- 已有任务的来源分支留空，执行时使用版本库配置的默认来源分支
+ALTER TABLE tasks RENAME COLUMN branch TO target_branch;
+ALTER TABLE tasks ADD COLUMN source_branch TEXT NOT NULL DEFAULT '';
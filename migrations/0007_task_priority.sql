@@ -0,0 +1,3 @@
+-- 任务优先级与在等待队列中的位置（从 1 开始，不在队列中时为空）
+ALTER TABLE tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN queue_position INTEGER;
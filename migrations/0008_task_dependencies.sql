@@ -0,0 +1,2 @@
+-- 任务依赖的前置任务 ID，以逗号分隔，为空表示没有依赖
+ALTER TABLE tasks ADD COLUMN depends_on TEXT NOT NULL DEFAULT '';
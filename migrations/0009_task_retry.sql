@@ -0,0 +1,9 @@
+-- 任务的执行次数、单独指定的最大执行次数，以及等待重试时下一次执行的时间（Unix 秒）
+-- 已经开始执行过的任务记为第 1 次执行
+ALTER TABLE tasks ADD COLUMN attempt INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN max_attempts INTEGER;
+ALTER TABLE tasks ADD COLUMN retry_at INTEGER;
+UPDATE tasks SET attempt = 1 WHERE status NOT IN ('Pending', 'Blocked', 'Queued', 'Cancelled');
+
+-- 每行日志所属的执行次数，用于区分各次执行的输出
+ALTER TABLE task_logs ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1;
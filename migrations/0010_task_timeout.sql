@@ -0,0 +1,3 @@
+-- 单独指定的执行超时时间，以及最近一次执行所用的时间（秒）
+ALTER TABLE tasks ADD COLUMN timeout_secs INTEGER;
+ALTER TABLE tasks ADD COLUMN elapsed_secs INTEGER;
@@ -0,0 +1,21 @@
+-- 一次性延后执行的任务的执行时间，以及由定时合并创建的任务对应的定时任务
+ALTER TABLE tasks ADD COLUMN run_at TEXT;
+ALTER TABLE tasks ADD COLUMN schedule_id INTEGER;
+
+-- 定时合并：按 cron 表达式定期把来源分支上未合并的修订创建为任务
+CREATE TABLE IF NOT EXISTS schedules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at TEXT NOT NULL,
+    name TEXT NOT NULL,
+    cron TEXT NOT NULL,
+    creator TEXT NOT NULL,
+    repository TEXT NOT NULL,
+    source_branch TEXT NOT NULL,
+    target_branch TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    missed TEXT NOT NULL DEFAULT 'CatchUp',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    next_run TEXT,
+    last_run TEXT,
+    last_task_id INTEGER
+);
@@ -0,0 +1,5 @@
+-- 任务类型：Merge 为版本库合并，Command 为执行服务端配置的脚本
+-- 命令任务记录脚本名称及以 JSON 对象保存的参数，版本库与分支等字段留空
+ALTER TABLE tasks ADD COLUMN kind TEXT NOT NULL DEFAULT 'Merge';
+ALTER TABLE tasks ADD COLUMN command TEXT NOT NULL DEFAULT '';
+ALTER TABLE tasks ADD COLUMN parameters TEXT NOT NULL DEFAULT '';
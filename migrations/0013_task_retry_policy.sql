@@ -0,0 +1,5 @@
+-- 单独指定的重试等待时间与可重试的失败类型（JSON 数组），为空时使用全局的重试配置
+-- 手动重试时记录已执行的次数，自动重试的次数从该次数之后重新计算
+ALTER TABLE tasks ADD COLUMN backoff_secs INTEGER;
+ALTER TABLE tasks ADD COLUMN retry_on TEXT;
+ALTER TABLE tasks ADD COLUMN attempts_before_retry INTEGER NOT NULL DEFAULT 0;
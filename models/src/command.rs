@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+// 服务端配置的一个可作为任务运行的脚本
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct CommandInfo {
+    pub name: String,
+    pub description: String,
+    pub parameters: Vec<String>, // 可以传入的参数名称
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+use crate::task::Task;
+
+// 通过 /api/events 推送给前端的任务变化
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", content = "data")]
+pub enum TaskUpdate {
+    Snapshot(Vec<Task>),  // 连接建立或推送落后时发送完整列表
+    Created(Task),
+    StatusChanged(Task),
+    Deleted(i64),
+}
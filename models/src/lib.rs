@@ -1,2 +1,8 @@
+pub mod command;
+pub mod event;
+pub mod log;
+pub mod revision;
+pub mod schedule;
 pub mod task;
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod vcs;
\ No newline at end of file
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// 任务执行过程中产生的一行日志
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, FromRow)]
+pub struct TaskLog {
+    pub task_id: i64,
+    pub seq: i64,            // 任务内递增的序号
+    pub attempt: i64,        // 所属的执行次数，执行前产生的日志为 0
+    pub timestamp: String,
+    pub stream: String,      // stdout / stderr / system
+    pub line: String,
+}
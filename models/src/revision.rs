@@ -0,0 +1,186 @@
+use std::fmt;
+
+// 一段连续的 SVN 修订，start == end 时表示单个修订
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RevisionRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl RevisionRange {
+    pub fn contains(&self, revision: i64) -> bool {
+        self.start <= revision && revision <= self.end
+    }
+}
+
+impl fmt::Display for RevisionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+// 解析修订列表时的错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevisionError {
+    Empty,
+    Invalid(String),       // 无法识别的写法
+    ReversedRange(String), // 起始修订大于结束修订
+}
+
+impl fmt::Display for RevisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevisionError::Empty => write!(f, "no revisions given"),
+            RevisionError::Invalid(part) => write!(f, "invalid revision {:?}", part),
+            RevisionError::ReversedRange(part) => write!(f, "revision range {:?} is reversed", part),
+        }
+    }
+}
+
+impl std::error::Error for RevisionError {}
+
+// 一组 SVN 修订，支持 1234、1234,1240、1200-1250 及 r1234 等写法
+//
+// 内部始终保持规范形式：各段按修订号排序，重叠或相邻的段合并，
+// 因此相同的修订集合总是得到相同的文本，例如 "r1240, 1234,1235" 规范为 "1234-1235,1240"
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct RevisionSet {
+    ranges: Vec<RevisionRange>,
+}
+
+impl RevisionSet {
+    pub fn parse(text: &str) -> Result<Self, RevisionError> {
+        let mut set = RevisionSet::default();
+        for part in text.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let range = match part.split_once('-') {
+                Some((start, end)) => {
+                    let range = RevisionRange {
+                        start: parse_revision(start).ok_or_else(|| RevisionError::Invalid(part.to_string()))?,
+                        end: parse_revision(end).ok_or_else(|| RevisionError::Invalid(part.to_string()))?,
+                    };
+                    if range.start > range.end {
+                        return Err(RevisionError::ReversedRange(part.to_string()));
+                    }
+                    range
+                }
+                None => {
+                    let revision = parse_revision(part).ok_or_else(|| RevisionError::Invalid(part.to_string()))?;
+                    RevisionRange { start: revision, end: revision }
+                }
+            };
+            set.ranges.push(range);
+        }
+        if set.ranges.is_empty() {
+            return Err(RevisionError::Empty);
+        }
+        set.normalize();
+        Ok(set)
+    }
+
+    pub fn ranges(&self) -> &[RevisionRange] {
+        &self.ranges
+    }
+
+    pub fn contains(&self, revision: i64) -> bool {
+        self.ranges.iter().any(|range| range.contains(revision))
+    }
+
+    pub fn insert(&mut self, revision: i64) {
+        self.ranges.push(RevisionRange { start: revision, end: revision });
+        self.normalize();
+    }
+
+    // 移除单个修订，必要时把所在的段拆成两段
+    pub fn remove(&mut self, revision: i64) {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + 1);
+        for range in self.ranges.drain(..) {
+            if !range.contains(revision) {
+                ranges.push(range);
+                continue;
+            }
+            if range.start < revision {
+                ranges.push(RevisionRange { start: range.start, end: revision - 1 });
+            }
+            if revision < range.end {
+                ranges.push(RevisionRange { start: revision + 1, end: range.end });
+            }
+        }
+        self.ranges = ranges;
+    }
+
+    // 排序并合并重叠或相邻的段
+    fn normalize(&mut self) {
+        self.ranges.sort();
+        let mut merged: Vec<RevisionRange> = Vec::with_capacity(self.ranges.len());
+        for range in self.ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end.saturating_add(1) => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        self.ranges = merged;
+    }
+}
+
+impl fmt::Display for RevisionSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, range) in self.ranges.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        Ok(())
+    }
+}
+
+// 单个修订号，允许 r 前缀，修订号从 1 开始
+fn parse_revision(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let text = text.strip_prefix('r').or_else(|| text.strip_prefix('R')).unwrap_or(text);
+    text.parse().ok().filter(|revision| *revision > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_normalizes_ranges() {
+        assert_eq!(RevisionSet::parse("1-3,r5, 4").unwrap().to_string(), "1-5");
+        assert_eq!(RevisionSet::parse("r1240, 1234,1235").unwrap().to_string(), "1234-1235,1240");
+        assert_eq!(RevisionSet::parse("10-20,15-30,R40").unwrap().to_string(), "10-30,40");
+        assert_eq!(RevisionSet::parse("7,7,7").unwrap().to_string(), "7");
+    }
+
+    #[test]
+    fn parse_rejects_invalid_lists() {
+        assert_eq!(RevisionSet::parse(" , "), Err(RevisionError::Empty));
+        assert_eq!(RevisionSet::parse("abc"), Err(RevisionError::Invalid("abc".to_string())));
+        assert_eq!(RevisionSet::parse("0"), Err(RevisionError::Invalid("0".to_string())));
+        assert_eq!(RevisionSet::parse("9-3"), Err(RevisionError::ReversedRange("9-3".to_string())));
+    }
+
+    #[test]
+    fn parse_accepts_the_largest_revision() {
+        let max = i64::MAX;
+        let set = RevisionSet::parse(&format!("{},1-{}", max, max)).unwrap();
+        assert_eq!(set.to_string(), format!("1-{}", max));
+    }
+
+    #[test]
+    fn insert_and_remove_keep_the_set_normalized() {
+        let mut set = RevisionSet::parse("1-5").unwrap();
+        set.remove(3);
+        assert_eq!(set.to_string(), "1-2,4-5");
+        set.insert(3);
+        assert_eq!(set.to_string(), "1-5");
+        set.insert(6);
+        assert_eq!(set.to_string(), "1-6");
+        assert!(set.contains(6) && !set.contains(7));
+    }
+}
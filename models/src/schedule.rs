@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// 服务停止期间错过的执行时间的处理方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRuns {
+    #[default]
+    CatchUp, // 恢复后补执行一次，多次错过也只执行一次
+    Skip,    // 跳过，等到下一个执行时间
+}
+
+// 创建定时合并任务的请求
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct NewSchedule {
+    pub name: String,
+    pub cron: String, // 5 段（分 时 日 月 周）或带秒的 6 段 cron 表达式，按服务器本地时间计算
+    pub creator: String,
+    #[serde(default)]
+    pub repository: String, // 版本库名称，为空时使用默认版本库
+    #[serde(default)]
+    pub source_branch: String, // 来源分支，为空时使用版本库配置的默认来源分支
+    pub target_branch: String,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub missed: MissedRuns,
+}
+
+// 定时合并任务：每到执行时间，把来源分支上尚未合并到目标分支的全部修订创建为一个任务
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, FromRow)]
+pub struct Schedule {
+    pub id: i64,
+    pub created_at: String,
+    pub name: String,
+    pub cron: String,
+    pub creator: String,
+    pub repository: String,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub priority: i32,
+    pub missed: MissedRuns,
+    pub enabled: bool,
+    pub next_run: Option<String>,    // 下一次执行时间（UTC），与 created_at 格式相同
+    pub last_run: Option<String>,    // 最近一次到期处理的时间（UTC）
+    pub last_task_id: Option<i64>,   // 最近一次创建的任务
+}
@@ -1,11 +1,189 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+// 任务类型，以变体名称的文本形式存入数据库
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    #[default]
+    Merge,   // 把来源分支上的修订合并到目标分支
+    Command, // 执行服务端配置的脚本
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, FromRow)]
 pub struct NewTask {
     pub creator: String,
-    pub branch: String,
-    pub svn_merge_number: String,
+    #[serde(default)]
+    pub kind: TaskKind,
+    #[serde(default)]
+    pub repository: String, // 版本库名称，为空时使用默认版本库
+    #[serde(default)]
+    pub source_branch: String, // 来源分支，为空时使用版本库配置的默认来源分支
+    #[serde(default, alias = "branch")]
+    pub target_branch: String,
+    #[serde(default, alias = "svn_merge_number")]
+    pub revisions: String,  // SVN 为修订号或修订范围（如 1234,1200-1250），Git 为提交哈希，均以逗号分隔
+    #[serde(default)]
+    pub priority: i32,      // 数值越大越先执行，默认 0
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub depends_on: Vec<i64>, // 前置任务 ID，全部成功后才开始执行
+    #[serde(default)]
+    pub max_attempts: Option<u32>, // 最多执行的次数，为空时使用全局的重试配置
+    #[serde(default)]
+    pub backoff_secs: Option<u64>, // 第一次重试前的等待时间，为空时使用全局的重试配置
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub retry_on: Option<Vec<FailureClass>>, // 可以自动重试的失败类型，为空时使用全局的重试配置
+    #[serde(default)]
+    pub timeout_secs: Option<u64>, // 每次执行的超时时间，为空时使用全局配置，0 表示不限制
+    #[serde(default)]
+    pub run_at: Option<String>, // 延后到该时间执行，RFC 3339 格式，或不带时区的服务器本地时间
+    #[serde(default)]
+    pub command: String, // 命令任务要执行的脚本名称
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub parameters: BTreeMap<String, String>, // 命令任务的参数
+    #[serde(default)]
+    pub dry_run: bool,      // 为 true 时只预览合并结果，不创建任务
+}
+
+// 任务失败的类型，用于决定是否自动重试
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureClass {
+    Locked,   // 工作副本或版本库被锁定
+    Network,  // 无法连接版本库服务器
+    Command,  // 其他命令失败
+    Conflict, // 合并产生冲突
+    Timeout,  // 执行超时
+    Error,    // 命令无法启动等其他错误
+}
+
+// 调整排队中任务在队列中的位置
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueMove {
+    Front,
+    Back,
+    Position(usize), // 从 1 开始的目标位置，超出队列长度时移到队尾
+}
+
+// 任务状态，以变体名称的文本形式存入数据库
+//
+// 状态转换表（未列出的转换均不允许）：
+//
+// | 当前状态    | 允许转换到                                           |
+// |-------------|------------------------------------------------------|
+// | Pending     | Queued, Blocked, Scheduled, Cancelled                |
+// | Scheduled   | Queued, Blocked, Cancelled                           |
+// | Blocked     | Queued, Cancelled (前置任务未成功时级联取消)         |
+// | Queued      | Running, Cancelled                                   |
+// | Running     | Succeeded, Failed, Stopped, Conflicted, Interrupted, |
+// |             | TimedOut, Retrying, Queued (重启后重新排队)         |
+// | Retrying    | Queued, Cancelled                                    |
+// | Succeeded   | -                                                    |
+// | Failed      | -                                                    |
+// | Stopped     | -                                                    |
+// | Cancelled   | -                                                    |
+// | Conflicted  | -                                                    |
+// | Interrupted | -                                                    |
+// | TimedOut    | -                                                    |
+//
+// Failed、Conflicted、Stopped、Interrupted、TimedOut、Cancelled 的任务可以通过 /api/tasks/:id/retry
+// 手动重试，任务回到 Pending 后重新被处理器接收，不受上表限制
+//
+// 运行中的任务不能删除，需要先停止
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default, sqlx::Type)]
+pub enum TaskStatus {
+    #[default]
+    Pending,    // 已创建，尚未被处理器接收
+    Scheduled,  // 等待 run_at 指定的执行时间
+    Blocked,    // 等待前置任务成功
+    Queued,     // 在处理器队列中等待执行
+    Running,
+    Retrying,   // 执行失败，等待自动重试
+    Succeeded,
+    Failed,
+    Stopped,    // 运行中被用户停止
+    Cancelled,  // 执行前被取消
+    Conflicted, // 合并产生冲突
+    Interrupted, // 执行期间服务重启
+    TimedOut,   // 执行超时，命令已被结束
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 13] = [
+        TaskStatus::Pending,
+        TaskStatus::Scheduled,
+        TaskStatus::Blocked,
+        TaskStatus::Queued,
+        TaskStatus::Running,
+        TaskStatus::Retrying,
+        TaskStatus::Succeeded,
+        TaskStatus::Failed,
+        TaskStatus::Stopped,
+        TaskStatus::Cancelled,
+        TaskStatus::Conflicted,
+        TaskStatus::Interrupted,
+        TaskStatus::TimedOut,
+    ];
+
+    // 按状态转换表判断能否从当前状态转换到 next
+    pub fn can_transition_to(self, next: TaskStatus) -> bool {
+        use TaskStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Queued | Blocked | Scheduled | Cancelled | Failed)
+                | (Scheduled, Queued | Blocked | Cancelled)
+                | (Blocked, Queued | Cancelled)
+                | (Queued, Running | Cancelled)
+                | (Running, Succeeded | Failed | Stopped | Conflicted | Interrupted | TimedOut | Retrying | Queued)
+                | (Retrying, Queued | Cancelled)
+        )
+    }
+
+    // 终态的任务不会再被执行
+    pub fn is_terminal(self) -> bool {
+        TaskStatus::ALL.iter().all(|next| !self.can_transition_to(*next))
+    }
+
+    // 未成功结束的任务可以手动重试
+    pub fn can_retry(self) -> bool {
+        use TaskStatus::*;
+        matches!(self, Failed | Conflicted | Stopped | Interrupted | TimedOut | Cancelled)
+    }
+
+    pub fn can_delete(self) -> bool {
+        self != TaskStatus::Running
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "Pending",
+            TaskStatus::Scheduled => "Scheduled",
+            TaskStatus::Blocked => "Blocked",
+            TaskStatus::Queued => "Queued",
+            TaskStatus::Running => "Running",
+            TaskStatus::Retrying => "Retrying",
+            TaskStatus::Succeeded => "Succeeded",
+            TaskStatus::Failed => "Failed",
+            TaskStatus::Stopped => "Stopped",
+            TaskStatus::Cancelled => "Cancelled",
+            TaskStatus::Conflicted => "Conflicted",
+            TaskStatus::Interrupted => "Interrupted",
+            TaskStatus::TimedOut => "TimedOut",
+        }
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, FromRow)]
@@ -13,7 +191,82 @@ pub struct Task {
     pub id: i64,
     pub created_at: String,
     pub creator: String,
-    pub branch: String,
-    pub svn_merge_number: String,
-    pub status: String,
+    pub kind: TaskKind,
+    pub command: String,    // 命令任务的脚本名称
+    pub parameters: String, // 命令任务的参数，JSON 对象
+    pub repository: String,
+    pub source_branch: String, // 旧任务可能为空，表示版本库的默认来源分支
+    pub target_branch: String,
+    pub revisions: String, // 创建时校验并规范化后的修订列表
+    pub status: TaskStatus,
+    pub priority: i32,
+    pub depends_on: String,           // 前置任务 ID，以逗号分隔
+    pub queue_position: Option<i64>,  // 在等待队列中的位置，从 1 开始
+    pub attempt: i64,                 // 已开始执行的次数
+    pub max_attempts: Option<i64>,    // 单独指定的最大执行次数
+    pub backoff_secs: Option<i64>,    // 单独指定的第一次重试前的等待时间
+    pub retry_on: Option<String>,     // 单独指定的可重试失败类型，JSON 数组
+    pub attempts_before_retry: i64,   // 最近一次手动重试时已执行的次数
+    pub retry_at: Option<i64>,        // 等待重试时下一次执行的时间（Unix 秒）
+    pub timeout_secs: Option<i64>,    // 单独指定的执行超时时间
+    pub elapsed_secs: Option<i64>,    // 最近一次执行所用的时间
+    pub run_at: Option<String>,       // 延后执行的时间（UTC），与 created_at 格式相同
+    pub schedule_id: Option<i64>,     // 由定时合并创建时对应的定时任务
+    pub exit_code: Option<i32>,       // 执行结束后的退出码
+    pub finished_at: Option<String>,
+}
+
+impl Task {
+    // 解析 depends_on 中的前置任务 ID
+    pub fn dependencies(&self) -> Vec<i64> {
+        self.depends_on
+            .split(',')
+            .filter_map(|id| id.trim().parse().ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_follow_the_table() {
+        use TaskStatus::*;
+        assert!(Pending.can_transition_to(Queued));
+        assert!(Pending.can_transition_to(Scheduled));
+        assert!(Pending.can_transition_to(Failed));
+        assert!(Scheduled.can_transition_to(Blocked));
+        assert!(Blocked.can_transition_to(Cancelled));
+        assert!(Queued.can_transition_to(Running));
+        assert!(Running.can_transition_to(Retrying));
+        assert!(Running.can_transition_to(Queued));
+        assert!(Retrying.can_transition_to(Queued));
+
+        assert!(!Pending.can_transition_to(Running));
+        assert!(!Blocked.can_transition_to(Running));
+        assert!(!Queued.can_transition_to(Succeeded));
+        assert!(!Running.can_transition_to(Cancelled));
+        assert!(!Retrying.can_transition_to(Running));
+    }
+
+    #[test]
+    fn terminal_states_have_no_transitions() {
+        let terminal: Vec<TaskStatus> = TaskStatus::ALL.into_iter().filter(|status| status.is_terminal()).collect();
+        assert_eq!(
+            terminal,
+            [
+                TaskStatus::Succeeded,
+                TaskStatus::Failed,
+                TaskStatus::Stopped,
+                TaskStatus::Cancelled,
+                TaskStatus::Conflicted,
+                TaskStatus::Interrupted,
+                TaskStatus::TimedOut,
+            ]
+        );
+        for status in TaskStatus::ALL {
+            assert!(!status.can_transition_to(status));
+        }
+    }
 }
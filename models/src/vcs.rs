@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// 版本库类型
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VcsKind {
+    #[default]
+    Svn,
+    Git,
+}
+
+// 服务端配置的一个版本库
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct Repository {
+    pub name: String,
+    pub kind: VcsKind,
+    pub merge_source: String, // 默认的合并来源分支
+}
+
+// 版本库中的一个分支及其最后一次提交信息
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct Branch {
+    pub name: String,
+    pub revision: String, // SVN 为修订号，Git 为提交哈希
+    pub author: String,
+    pub date: String,
+}
+
+// 提交中改动的一个路径
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct ChangedPath {
+    pub action: String,   // A / M / D / R
+    pub path: String,
+}
+
+// 分支上的一次提交
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct Commit {
+    pub revision: String, // SVN 为修订号，Git 为提交哈希
+    pub author: String,
+    pub date: String,
+    pub message: String,
+    pub changed_paths: Vec<ChangedPath>,
+}
+
+// 合并后处于冲突状态的一个路径
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, FromRow)]
+pub struct Conflict {
+    pub path: String,
+    pub kind: String, // text / property / tree
+}
+
+// 试合并的结果，不会提交也不会创建任务
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct MergePreview {
+    pub revisions: String,             // 规范化后的修订列表
+    pub paths: Vec<ChangedPath>,       // 合并会改动的路径
+    pub conflicts: Vec<Conflict>,      // 预计产生的冲突
+    pub commit_message: String,        // 按模板生成的提交信息
+}
+
+// 服务端保留的一个工作副本
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct WorkcopyStatus {
+    pub repository: String,
+    pub branch: String,         // 本次运行中未使用过时为目录名
+    pub path: String,
+    pub exists: bool,
+    pub in_use: bool,
+    pub holder: Option<String>, // 当前使用者，如 "task 12"
+    pub idle_secs: Option<u64>, // 距最后一次使用的秒数
+}
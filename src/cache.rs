@@ -0,0 +1,50 @@
+// src/cache.rs
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+// 带过期时间的单值缓存，用于缓存代价较高的版本库查询
+pub struct TtlCache<T> {
+    ttl: Duration,
+    entry: Arc<Mutex<Option<(Instant, T)>>>,
+}
+
+impl<T> Clone for TtlCache<T> {
+    fn clone(&self) -> Self {
+        TtlCache {
+            ttl: self.ttl,
+            entry: self.entry.clone(),
+        }
+    }
+}
+
+impl<T: Clone> TtlCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entry: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // 缓存未过期时直接返回，否则（或 refresh 为 true 时）调用 load 重新加载
+    // 加载期间持有锁，避免并发请求重复查询
+    pub async fn get_or_load<F, Fut, E>(&self, refresh: bool, load: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut entry = self.entry.lock().await;
+        if let Some((loaded_at, value)) = entry.as_ref() {
+            if !refresh && loaded_at.elapsed() < self.ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = load().await?;
+        *entry = Some((Instant::now(), value.clone()));
+        Ok(value)
+    }
+}
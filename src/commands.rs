@@ -0,0 +1,139 @@
+// src/commands.rs
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use models::command::CommandInfo;
+
+use crate::config::CommandConfig;
+
+// 所有已配置的脚本
+pub struct Commands {
+    commands: Vec<Arc<CommandConfig>>,
+}
+
+impl Commands {
+    pub fn new(configs: Vec<CommandConfig>) -> Self {
+        Commands {
+            commands: configs.into_iter().map(Arc::new).collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<CommandConfig>> {
+        self.commands.iter().find(|command| command.name == name).cloned()
+    }
+
+    pub fn info(&self) -> Vec<CommandInfo> {
+        self.commands
+            .iter()
+            .map(|command| CommandInfo {
+                name: command.name.clone(),
+                description: command.description.clone(),
+                parameters: command.parameters.clone(),
+            })
+            .collect()
+    }
+}
+
+// 检查脚本配置：参数名只含字母、数字和下划线，命令行中引用的参数都已声明
+pub fn validate_config(command: &CommandConfig) -> anyhow::Result<()> {
+    if command.name.is_empty() {
+        anyhow::bail!("command name is required");
+    }
+    if command.program.as_os_str().is_empty() {
+        anyhow::bail!("program is required");
+    }
+    for name in &command.parameters {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("invalid parameter name {:?}", name);
+        }
+    }
+    for arg in &command.args {
+        let mut rest = arg.as_str();
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                anyhow::bail!("unclosed placeholder in argument {:?}", arg);
+            };
+            let name = &rest[start + 1..start + end];
+            if !command.parameters.iter().any(|parameter| parameter == name) {
+                anyhow::bail!("argument {:?} uses undeclared parameter {{{}}}", arg, name);
+            }
+            rest = &rest[start + end + 1..];
+        }
+    }
+    Ok(())
+}
+
+// 检查任务传入的参数都已声明；值不能以 - 开头以免被当作选项，也不能含控制字符
+pub fn validate_parameters(command: &CommandConfig, parameters: &BTreeMap<String, String>) -> Result<(), String> {
+    for (name, value) in parameters {
+        if !command.parameters.contains(name) {
+            return Err(format!("Command {} has no parameter {}", command.name, name));
+        }
+        if value.starts_with('-') || value.chars().any(char::is_control) {
+            return Err(format!("Invalid value for parameter {}", name));
+        }
+    }
+    Ok(())
+}
+
+// 把参数替换到命令行中，未传入的参数替换为空
+pub fn render_args(command: &CommandConfig, parameters: &BTreeMap<String, String>) -> Vec<String> {
+    command.args.iter().map(|arg| render_arg(arg, command, parameters)).collect()
+}
+
+// 逐个扫描 {参数名}，替换进来的值不再展开
+fn render_arg(arg: &str, command: &CommandConfig, parameters: &BTreeMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let name = rest
+            .find('}')
+            .map(|end| &rest[1..end])
+            .filter(|name| command.parameters.iter().any(|parameter| parameter == name));
+        match name {
+            Some(name) => {
+                rendered.push_str(parameters.get(name).map(String::as_str).unwrap_or_default());
+                rest = &rest[name.len() + 2..];
+            }
+            // 不是已配置的参数，按原样输出
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> CommandConfig {
+        CommandConfig {
+            name: "deploy".to_string(),
+            args: vec!["--env={env}".to_string(), "{tag}".to_string(), "{unknown} {env".to_string()],
+            parameters: vec!["env".to_string(), "tag".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn render_args_fills_parameters() {
+        let parameters = BTreeMap::from([("env".to_string(), "prod".to_string())]);
+        assert_eq!(render_args(&command(), &parameters), ["--env=prod", "", "{unknown} {env"]);
+    }
+
+    #[test]
+    fn render_args_does_not_expand_values() {
+        let parameters = BTreeMap::from([
+            ("env".to_string(), "{tag}".to_string()),
+            ("tag".to_string(), "v1".to_string()),
+        ]);
+        assert_eq!(render_args(&command(), &parameters), ["--env={tag}", "v1", "{unknown} {env"]);
+    }
+}
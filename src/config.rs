@@ -0,0 +1,191 @@
+// src/config.rs
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+
+use models::task::FailureClass;
+use models::vcs::VcsKind;
+
+use crate::commands;
+use crate::message;
+use crate::vcs::pool;
+use serde::Deserialize;
+
+// 服务配置，从 config.toml 读取，缺省时使用默认值
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub database_url: String, // SQLite 数据库地址，文件不存在时自动创建
+    pub repositories: Vec<RepositoryConfig>, // 第一个为默认版本库
+    pub commands: Vec<CommandConfig>,         // 可以作为任务运行的脚本，只有列在这里的脚本能被执行
+    pub processor: ProcessorConfig,
+    pub workcopies: WorkcopyConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            database_url: "sqlite:task.db".to_string(),
+            repositories: vec![RepositoryConfig::default()],
+            commands: vec![],
+            processor: ProcessorConfig::default(),
+            workcopies: WorkcopyConfig::default(),
+        }
+    }
+}
+
+// 版本库及工作副本相关配置
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RepositoryConfig {
+    pub name: String,
+    pub kind: VcsKind,
+    pub url: String,                 // SVN 为仓库根地址（其下有 trunk/ 与 branches/），Git 为远程地址
+    pub merge_source: String,        // 默认的合并来源分支
+    pub workcopy_dir: PathBuf,       // 存放工作副本的目录，每个版本库使用以名称命名的子目录
+    pub commit_template: String,     // 合并提交信息的模板，可用的占位符见 message::PLACEHOLDERS
+    pub branch_cache_ttl_secs: u64,  // 分支列表的缓存时间
+    pub username: Option<String>,    // 仅用于 SVN，Git 请使用凭据助手
+    pub password: Option<String>,
+}
+
+impl Default for RepositoryConfig {
+    fn default() -> Self {
+        RepositoryConfig {
+            name: "default".to_string(),
+            kind: VcsKind::Svn,
+            url: "file:///srv/svn/project".to_string(),
+            merge_source: "trunk".to_string(),
+            workcopy_dir: PathBuf::from("workcopies"),
+            commit_template: message::DEFAULT_TEMPLATE.to_string(),
+            branch_cache_ttl_secs: 60,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+impl RepositoryConfig {
+    // 分支对应的本地工作副本路径
+    pub fn workcopy_path(&self, branch: &str) -> PathBuf {
+        self.workcopy_dir.join(&self.name).join(pool::dir_name(branch))
+    }
+}
+
+// 可以作为任务运行的脚本，直接执行而不经过 shell
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct CommandConfig {
+    pub name: String,
+    pub description: String,
+    pub program: PathBuf,              // 脚本或程序的路径
+    pub args: Vec<String>,             // 命令行参数，可用 {参数名} 引用任务参数
+    pub parameters: Vec<String>,       // 任务可以传入的参数，同时以 TASK_PARAM_<大写参数名> 环境变量传给脚本
+    pub working_dir: Option<PathBuf>,  // 为空时使用服务的工作目录
+}
+
+// 工作副本回收相关配置
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WorkcopyConfig {
+    pub max_idle_secs: u64,    // 超过该时间未使用的工作副本会被删除，下次使用时重新检出
+    pub gc_interval_secs: u64, // 检查间隔，为 0 时不回收
+}
+
+impl Default for WorkcopyConfig {
+    fn default() -> Self {
+        WorkcopyConfig {
+            max_idle_secs: 7 * 24 * 3600,
+            gc_interval_secs: 3600,
+        }
+    }
+}
+
+// 任务处理器相关配置
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ProcessorConfig {
+    pub recovery: RecoveryPolicy,
+    pub max_parallel: usize, // 同时执行的任务数上限，目标分支相同的任务总是依次执行
+    pub timeout_secs: u64,   // 每次执行的超时时间，不含等待工作副本的时间，0 表示不限制；创建任务时可单独指定
+    pub retry: RetryConfig,
+}
+
+impl Default for ProcessorConfig {
+    fn default() -> Self {
+        ProcessorConfig {
+            recovery: RecoveryPolicy::default(),
+            max_parallel: 4,
+            timeout_secs: 3600,
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+// 任务失败后的自动重试
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_attempts: u32,           // 每个任务最多执行的次数，1 表示不自动重试；创建任务时可单独指定
+    pub backoff_secs: u64,           // 第一次重试前的等待时间，之后每次翻倍；创建任务时可单独指定
+    pub max_backoff_secs: u64,       // 等待时间的上限
+    pub retry_on: Vec<FailureClass>, // 可以自动重试的失败类型；创建任务时可单独指定
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 1,
+            backoff_secs: 30,
+            max_backoff_secs: 600,
+            retry_on: vec![FailureClass::Locked, FailureClass::Network],
+        }
+    }
+}
+
+impl RetryConfig {
+    // 第 attempt 次执行失败后到下一次执行前的等待时间
+    pub fn backoff(&self, attempt: i64) -> Duration {
+        let exponent = attempt.clamp(1, 32) as u32 - 1;
+        let secs = self.backoff_secs.saturating_mul(2_u64.saturating_pow(exponent));
+        Duration::from_secs(secs.min(self.max_backoff_secs))
+    }
+}
+
+// 服务重启时对仍处于 Running 状态的任务的处理方式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecoveryPolicy {
+    #[default]
+    Interrupt, // 标记为 Interrupted，需要人工处理
+    Requeue,   // 清理工作副本后重新排队执行
+}
+
+impl Config {
+    // 读取配置文件，文件不存在时返回默认配置
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            println!("config file {} not found, using defaults", path.display());
+            return Ok(Config::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&text)?;
+        if config.repositories.is_empty() {
+            config.repositories.push(RepositoryConfig::default());
+        }
+        for repository in &config.repositories {
+            message::validate_template(&repository.commit_template)
+                .with_context(|| format!("repository {}", repository.name))?;
+        }
+        for (index, command) in config.commands.iter().enumerate() {
+            commands::validate_config(command).with_context(|| format!("command {}", command.name))?;
+            if config.commands[..index].iter().any(|other| other.name == command.name) {
+                anyhow::bail!("command {} is configured more than once", command.name);
+            }
+        }
+        Ok(config)
+    }
+}
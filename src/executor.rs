@@ -0,0 +1,209 @@
+// src/executor.rs
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use models::{task::{FailureClass, Task}, vcs::Conflict};
+
+use crate::commands;
+use crate::config::CommandConfig;
+use crate::logs::TaskLogger;
+use crate::message::{self, MessageFields};
+use crate::vcs::{self, CommandFailed, Repository};
+
+// 合并任务的执行结果
+#[derive(Debug)]
+pub enum Outcome {
+    Succeeded,
+    Failed(i32, FailureClass), // 第一个失败命令的退出码及失败类型
+    Conflicted(Vec<Conflict>), // 合并产生冲突，工作副本已还原
+    TimedOut,                  // 执行超时，命令已被结束，工作副本已还原
+}
+
+// SVN 错误码或 Git 输出中表示锁定的内容
+const LOCKED_PATTERNS: [&str; 5] = ["E155004", "E155037", "E160035", "is already locked", "index.lock"];
+
+// SVN 错误码或 Git 输出中表示网络故障的内容
+const NETWORK_PATTERNS: [&str; 9] = [
+    "E170013",
+    "E175002",
+    "E000104",
+    "E000110",
+    "E000111",
+    "Could not resolve host",
+    "Connection timed out",
+    "Connection reset",
+    "early EOF",
+];
+
+// 按命令的错误输出判断失败类型
+fn failure_class(failed: &CommandFailed) -> FailureClass {
+    let matches = |patterns: &[&str]| patterns.iter().any(|pattern| failed.stderr.contains(pattern));
+    if matches(&LOCKED_PATTERNS) {
+        FailureClass::Locked
+    } else if matches(&NETWORK_PATTERNS) {
+        FailureClass::Network
+    } else {
+        FailureClass::Command
+    }
+}
+
+// 执行一个合并任务：准备工作副本、合并指定修订、提交
+// 超过 timeout 仍未完成时结束正在执行的命令并还原工作副本
+// 同时返回执行耗时，与超时一样从取得工作副本后开始计算，不含等待工作副本的时间
+pub async fn execute(
+    repository: &Repository,
+    task: &Task,
+    timeout: Option<Duration>,
+    log: &TaskLogger,
+) -> (anyhow::Result<Outcome>, Duration) {
+    // 预览等操作可能正在使用同一个工作副本
+    let holder = format!("task {}", task.id);
+    let _guard = match repository.workcopies.try_lock(&task.target_branch, &holder) {
+        Some(guard) => guard,
+        None => {
+            log.system("Waiting for the working copy to become available").await;
+            repository.workcopies.lock(&task.target_branch, &holder).await
+        }
+    };
+    let started = Instant::now();
+    let result = merge_within(repository, task, timeout, log).await;
+    (result, started.elapsed())
+}
+
+// 在 timeout 内完成合并，失败的命令按失败类型转为 Outcome::Failed
+async fn merge_within(repository: &Repository, task: &Task, timeout: Option<Duration>, log: &TaskLogger) -> anyhow::Result<Outcome> {
+    let result = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, merge(repository, task, log)).await {
+            Ok(result) => result,
+            // 超时后合并的 future 被丢弃，其中的命令随之被结束
+            Err(_) => {
+                log.system(&format!("Task timed out after {} seconds, reverting working copy", timeout.as_secs())).await;
+                let workcopy = repository.vcs.workcopy_path(&task.target_branch);
+                if workcopy.exists() {
+                    if let Err(e) = repository.vcs.revert(&workcopy, log).await {
+                        log.system(&format!("Failed to revert working copy: {:#}", e)).await;
+                    }
+                }
+                return Ok(Outcome::TimedOut);
+            }
+        },
+        None => merge(repository, task, log).await,
+    };
+    match result {
+        Ok(Outcome::Succeeded) => {
+            log.system("Merge committed").await;
+            Ok(Outcome::Succeeded)
+        }
+        Ok(outcome) => Ok(outcome),
+        Err(e) => match e.downcast_ref::<CommandFailed>() {
+            Some(failed) => {
+                let class = failure_class(failed);
+                log.system(&format!("{} failed with exit code {} ({:?})", failed.command, failed.code, class)).await;
+                Ok(Outcome::Failed(failed.code, class))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+// 执行一个命令任务：运行配置的脚本，参数替换到命令行并以环境变量传入
+// 超过 timeout 仍未完成时结束脚本及其派生的进程
+pub async fn run_script(command: &CommandConfig, task: &Task, timeout: Option<Duration>, log: &TaskLogger) -> anyhow::Result<Outcome> {
+    let parameters: BTreeMap<String, String> = if task.parameters.is_empty() {
+        BTreeMap::new()
+    } else {
+        serde_json::from_str(&task.parameters)?
+    };
+    let args = commands::render_args(command, &parameters);
+    let display = std::iter::once(command.program.display().to_string())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut process = tokio::process::Command::new(&command.program);
+    process.args(&args).env("TASK_ID", task.id.to_string());
+    for (name, value) in &parameters {
+        process.env(format!("TASK_PARAM_{}", name.to_uppercase()), value);
+    }
+    if let Some(working_dir) = &command.working_dir {
+        process.current_dir(working_dir);
+    }
+
+    let run = vcs::run_command(process, &display, Some(log));
+    let output = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, run).await {
+            Ok(output) => output?,
+            Err(_) => {
+                log.system(&format!("Task timed out after {} seconds", timeout.as_secs())).await;
+                return Ok(Outcome::TimedOut);
+            }
+        },
+        None => run.await?,
+    };
+    if output.success() {
+        log.system("Command finished").await;
+        Ok(Outcome::Succeeded)
+    } else {
+        log.system(&format!("{} failed with exit code {}", command.name, output.code)).await;
+        Ok(Outcome::Failed(output.code, FailureClass::Command))
+    }
+}
+
+async fn merge(repository: &Repository, task: &Task, log: &TaskLogger) -> anyhow::Result<Outcome> {
+    let vcs = &repository.vcs;
+    let source_branch = repository.source_branch(&task.source_branch);
+    let revisions: Vec<String> = task
+        .revisions
+        .split(',')
+        .map(|revision| revision.trim().to_string())
+        .filter(|revision| !revision.is_empty())
+        .collect();
+
+    let workcopy = vcs.prepare_workcopy(&task.target_branch, log).await?;
+    let merged = vcs.merge_revisions(&workcopy, source_branch, &revisions, log).await;
+
+    // 有冲突时不论合并命令是否成功都视为冲突；两种情况都不提交，并还原工作副本
+    let conflicts = match vcs.conflicts(&workcopy).await {
+        Ok(conflicts) => conflicts,
+        Err(e) => {
+            vcs.revert(&workcopy, log).await?;
+            return Err(e);
+        }
+    };
+    if !conflicts.is_empty() {
+        for conflict in &conflicts {
+            log.system(&format!("Conflict ({}): {}", conflict.kind, conflict.path)).await;
+        }
+        vcs.revert(&workcopy, log).await?;
+        return Ok(Outcome::Conflicted(conflicts));
+    }
+    if let Err(e) = merged {
+        vcs.revert(&workcopy, log).await?;
+        return Err(e);
+    }
+
+    let message = commit_message(repository, task, log).await;
+    vcs.commit(&workcopy, &message, &revisions, log).await?;
+    Ok(Outcome::Succeeded)
+}
+
+// 按版本库的模板生成提交信息，读取原始提交信息失败时该部分留空
+async fn commit_message(repository: &Repository, task: &Task, log: &TaskLogger) -> String {
+    let fields = MessageFields {
+        kind: repository.kind,
+        repository: &repository.name,
+        source: repository.source_branch(&task.source_branch),
+        target: &task.target_branch,
+        creator: &task.creator,
+        revisions: &task.revisions,
+        task_id: Some(task.id),
+    };
+    match message::generate(repository, &fields).await {
+        Ok(message) => message,
+        Err(e) => {
+            log.system(&format!("Failed to read original commit messages: {:#}", e)).await;
+            message::render(&repository.commit_template, &fields, &[])
+        }
+    }
+}
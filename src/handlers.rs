@@ -1,117 +1,698 @@
 // src/handlers.rs
 
-use std::net::{SocketAddr, IpAddr};
+use std::{convert::Infallible, net::{SocketAddr, IpAddr}, sync::Arc};
 
 use axum::{
-    extract::{Extension, Path},
-    Json, response::IntoResponse, http::StatusCode,
+    extract::{Extension, Path, Query},
+    Json, response::{IntoResponse, sse::{Event, KeepAlive, Sse}}, http::StatusCode,
 };
 use sqlx::SqlitePool;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{broadcast::error::RecvError, mpsc::UnboundedSender, oneshot};
 
-use models::{task::{Task, NewTask}, user::User};
+use models::{command::CommandInfo, event::TaskUpdate, log::TaskLog, schedule::{NewSchedule, Schedule}, task::{Task, NewTask, QueueMove, TaskKind, TaskStatus}, user::User, vcs::{Conflict, MergePreview, Repository as RepositoryInfo, WorkcopyStatus}};
+use serde::{Deserialize, Serialize};
 
-use crate::tasks::TaskEvent;
+use crate::commands::{self, Commands};
+use crate::logs::{LogEvent, LogSender};
+use crate::message::{self, MessageFields};
+use crate::schedules;
+use crate::tasks::{self, Rejection, Reply, TaskEvent, UpdateSender};
+use crate::vcs::{self, InvalidRevisions, Repositories, Repository};
 
-pub async fn get_tasks(Extension(db_pool): Extension<SqlitePool>) -> Json<Vec<Task>> {
-    let tasks = sqlx::query_as::<_, Task>("SELECT * FROM tasks")
-        .fetch_all(&db_pool)
+// 将事件发送给任务处理器并等待其答复
+async fn send_event(
+    tx: &UnboundedSender<TaskEvent>,
+    event: impl FnOnce(Reply) -> TaskEvent,
+) -> Result<(), (StatusCode, String)> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(event(reply_tx))
+        .map_err(|_| (StatusCode::SERVICE_UNAVAILABLE, "Task processor is not running".to_string()))?;
+    match reply_rx.await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(rejection)) => {
+            let status = match rejection {
+                Rejection::NotFound(_) => StatusCode::NOT_FOUND,
+                Rejection::Conflict(_) => StatusCode::CONFLICT,
+                Rejection::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            Err((status, rejection.to_string()))
+        }
+        Err(_) => Err((StatusCode::SERVICE_UNAVAILABLE, "Task processor dropped the request".to_string())),
+    }
+}
+
+async fn fetch_tasks(db_pool: &SqlitePool) -> Vec<Task> {
+    sqlx::query_as::<_, Task>("SELECT * FROM tasks")
+        .fetch_all(db_pool)
         .await
-        .unwrap_or_else(|_| vec![]);
+        .unwrap_or_else(|_| vec![])
+}
 
-    Json(tasks)
+pub async fn get_tasks(Extension(db_pool): Extension<SqlitePool>) -> Json<Vec<Task>> {
+    Json(fetch_tasks(&db_pool).await)
+}
+
+fn update_event(update: &TaskUpdate) -> Event {
+    Event::default()
+        .event("task")
+        .json_data(update)
+        .unwrap_or_else(|_| Event::default().event("task"))
+}
+
+// 通过 SSE 推送任务变化：连接时先发送完整列表，之后推送增量变化
+pub async fn task_events(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(update_tx): Extension<UpdateSender>,
+) -> impl IntoResponse {
+    let mut receiver = update_tx.subscribe();
+    let stream = async_stream::stream! {
+        yield Ok::<_, Infallible>(update_event(&TaskUpdate::Snapshot(fetch_tasks(&db_pool).await)));
+        loop {
+            match receiver.recv().await {
+                Ok(update) => yield Ok(update_event(&update)),
+                // 推送落后时重新发送完整列表
+                Err(RecvError::Lagged(_)) => {
+                    yield Ok(update_event(&TaskUpdate::Snapshot(fetch_tasks(&db_pool).await)));
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
 }
 
 pub async fn create_task(
     Extension(db_pool): Extension<SqlitePool>,
     Extension(tx): Extension<UnboundedSender<TaskEvent>>,
+    Extension(repositories): Extension<Arc<Repositories>>,
+    Extension(commands): Extension<Arc<Commands>>,
     Json(new_task): Json<NewTask>,
 ) -> impl IntoResponse {
     if new_task.creator == "error" {
         return (StatusCode::INTERNAL_SERVER_ERROR, "测试错误！！！！！").into_response();
     }
-    let task: Task = sqlx::query_as(
+    // 合并任务保存版本库、分支和修订列表，命令任务保存参数，其余字段留空
+    let (repository, source_branch, target_branch, revisions, parameters) = match new_task.kind {
+        TaskKind::Merge => {
+            // 未指定版本库时使用默认版本库，保存实际名称
+            let repository = match find_repository(&repositories, &new_task.repository) {
+                Ok(repository) => repository,
+                Err(e) => return e.into_response(),
+            };
+            // 修订列表按来源分支的历史校验，以规范形式保存，已合并的修订会被拒绝
+            let (source_branch, revisions) = match validate_task(&repository, &new_task).await {
+                Ok(validated) => validated,
+                Err(e) => return e.into_response(),
+            };
+            if new_task.dry_run {
+                return match preview(&repository, &source_branch, &new_task, &revisions).await {
+                    Ok(preview) => Json(preview).into_response(),
+                    Err(e) => e.into_response(),
+                };
+            }
+            (repository.name.clone(), source_branch, new_task.target_branch.clone(), revisions, String::new())
+        }
+        TaskKind::Command => {
+            if new_task.dry_run {
+                return (StatusCode::BAD_REQUEST, "Dry run is only supported for merge tasks".to_string()).into_response();
+            }
+            match validate_command(&commands, &new_task) {
+                Ok(parameters) => (String::new(), String::new(), String::new(), String::new(), parameters),
+                Err(e) => return e.into_response(),
+            }
+        }
+    };
+    let depends_on = match validate_dependencies(&db_pool, &new_task.depends_on).await {
+        Ok(depends_on) => depends_on,
+        Err(e) => return e.into_response(),
+    };
+    // 延后执行的时间统一保存为 UTC
+    let run_at = match new_task.run_at.as_deref().filter(|run_at| !run_at.trim().is_empty()) {
+        Some(run_at) => match schedules::parse_run_at(run_at) {
+            Some(run_at) => Some(schedules::format_timestamp(run_at)),
+            None => return (StatusCode::BAD_REQUEST, format!("Invalid run_at {:?}", run_at)).into_response(),
+        },
+        None => None,
+    };
+    // 单独指定的可重试失败类型以 JSON 数组保存
+    let retry_on = match new_task.retry_on.as_ref().map(serde_json::to_string).transpose() {
+        Ok(retry_on) => retry_on,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save retry_on: {}", e)).into_response(),
+    };
+    let task: Task = match sqlx::query_as(
         r#"
-        INSERT INTO tasks (created_at, creator, branch, svn_merge_number, status)
-        VALUES (CURRENT_TIMESTAMP, ?1, ?2, ?3, ?4)
-        RETURNING id, created_at, creator, branch, svn_merge_number, status
+        INSERT INTO tasks (created_at, creator, kind, command, parameters, repository, source_branch, target_branch, revisions,
+                           priority, depends_on, max_attempts, backoff_secs, retry_on, timeout_secs, run_at, status)
+        VALUES (CURRENT_TIMESTAMP, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+        RETURNING *
         "#,
     )
     .bind(new_task.creator)
-    .bind(new_task.branch)
-    .bind(new_task.svn_merge_number)
-    .bind("Pending") // 默认状态值
+    .bind(new_task.kind)
+    .bind(new_task.command)
+    .bind(parameters)
+    .bind(repository)
+    .bind(source_branch)
+    .bind(target_branch)
+    .bind(revisions)
+    .bind(new_task.priority)
+    .bind(depends_on)
+    .bind(new_task.max_attempts)
+    .bind(new_task.backoff_secs.map(|secs| secs as i64))
+    .bind(retry_on)
+    .bind(new_task.timeout_secs.map(|secs| secs as i64))
+    .bind(run_at)
+    .bind(TaskStatus::Pending) // 默认状态值
     .fetch_one(&db_pool)
     .await
-    .unwrap_or_else(|e| {
-        eprintln!("Failed to create task: {}", e);
-        panic!("Failed to create task");
-    });
+    {
+        Ok(task) => task,
+        Err(e) => {
+            eprintln!("Failed to create task: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create task: {}", e)).into_response();
+        }
+    };
+
+    // 通知任务处理器将任务加入队列，未被接收时删除刚创建的记录
+    match send_event(&tx, |reply| TaskEvent::Create(Box::new(task.clone()), reply)).await {
+        Ok(()) => (StatusCode::CREATED, Json(task)).into_response(),
+        Err(e) => {
+            tasks::discard_pending(&db_pool, task.id).await;
+            e.into_response()
+        }
+    }
+}
+
+// 试合并任务，不创建任务记录
+pub async fn preview_task(
+    Extension(repositories): Extension<Arc<Repositories>>,
+    Json(new_task): Json<NewTask>,
+) -> impl IntoResponse {
+    let repository = match find_repository(&repositories, &new_task.repository) {
+        Ok(repository) => repository,
+        Err(e) => return e.into_response(),
+    };
+    let (source_branch, revisions) = match validate_task(&repository, &new_task).await {
+        Ok(validated) => validated,
+        Err(e) => return e.into_response(),
+    };
+    match preview(&repository, &source_branch, &new_task, &revisions).await {
+        Ok(preview) => Json(preview).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Serialize)]
+pub struct CommitMessage {
+    message: String,
+}
+
+// 预览任务执行时将使用的提交信息
+pub async fn preview_commit_message(
+    Extension(repositories): Extension<Arc<Repositories>>,
+    Json(new_task): Json<NewTask>,
+) -> impl IntoResponse {
+    let repository = match find_repository(&repositories, &new_task.repository) {
+        Ok(repository) => repository,
+        Err(e) => return e.into_response(),
+    };
+    let (source_branch, revisions) = match validate_task(&repository, &new_task).await {
+        Ok(validated) => validated,
+        Err(e) => return e.into_response(),
+    };
+    match commit_message(&repository, &source_branch, &new_task, &revisions).await {
+        Ok(message) => Json(CommitMessage { message }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn commit_message(
+    repository: &Repository,
+    source_branch: &str,
+    new_task: &NewTask,
+    revisions: &str,
+) -> Result<String, (StatusCode, String)> {
+    let fields = MessageFields {
+        kind: repository.kind,
+        repository: &repository.name,
+        source: source_branch,
+        target: &new_task.target_branch,
+        creator: &new_task.creator,
+        revisions,
+        task_id: None,
+    };
+    message::generate(repository, &fields)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate commit message: {:#}", e)))
+}
+
+// 确定来源分支，规范化修订列表，并确认其中没有已合并到目标分支的修订
+// 返回实际的来源分支与规范化后的修订列表
+async fn validate_task(repository: &Repository, new_task: &NewTask) -> Result<(String, String), (StatusCode, String)> {
+    let source_branch = repository.source_branch(&new_task.source_branch).to_string();
+    if new_task.target_branch.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Target branch is required".to_string()));
+    }
+    for branch in [&source_branch, &new_task.target_branch] {
+        vcs::check_branch_name(branch).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if source_branch == new_task.target_branch {
+        return Err((StatusCode::BAD_REQUEST, "Source and target branch must differ".to_string()));
+    }
+
+    let vcs = &repository.vcs;
+    let result = match vcs.normalize_revisions(&source_branch, &new_task.revisions).await {
+        Ok(revisions) => vcs
+            .ensure_eligible(&new_task.target_branch, &source_branch, &revisions)
+            .await
+            .map(|()| revisions),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(revisions) => Ok((source_branch, revisions)),
+        Err(e) => {
+            let status = match e.downcast_ref::<InvalidRevisions>() {
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            Err((status, format!("Invalid revisions: {:#}", e)))
+        }
+    }
+}
 
-    (StatusCode::CREATED, Json(task)).into_response()
+// 检查命令任务的脚本已配置且参数合法，返回以 JSON 对象保存的参数
+fn validate_command(commands: &Commands, new_task: &NewTask) -> Result<String, (StatusCode, String)> {
+    let Some(command) = commands.get(&new_task.command) else {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown command {:?}", new_task.command)));
+    };
+    commands::validate_parameters(&command, &new_task.parameters).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    serde_json::to_string(&new_task.parameters)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save parameters: {}", e)))
+}
+
+// 检查前置任务都存在且没有以失败等状态结束，并且沿前置任务向上不存在依赖环
+// 返回以逗号分隔的前置任务 ID
+async fn validate_dependencies(db_pool: &SqlitePool, depends_on: &[i64]) -> Result<String, (StatusCode, String)> {
+    let mut depends_on = depends_on.to_vec();
+    depends_on.sort();
+    depends_on.dedup();
+
+    if depends_on.is_empty() {
+        return Ok(String::new());
+    }
+
+    // 新任务只能依赖已存在的任务，不会形成环，只需一次查询检查前置任务的状态
+    let placeholders = vec!["?"; depends_on.len()].join(", ");
+    let sql = format!("SELECT id, status FROM tasks WHERE id IN ({})", placeholders);
+    let mut query = sqlx::query_as::<_, (i64, TaskStatus)>(&sql);
+    for &id in &depends_on {
+        query = query.bind(id);
+    }
+    let statuses = query
+        .fetch_all(db_pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check prerequisites: {}", e)))?;
+    for &id in &depends_on {
+        match statuses.iter().find(|(found, _)| *found == id) {
+            None => return Err((StatusCode::BAD_REQUEST, format!("Prerequisite task {} not found", id))),
+            Some((_, status)) if status.is_terminal() && *status != TaskStatus::Succeeded => {
+                return Err((StatusCode::BAD_REQUEST, format!("Prerequisite task {} is {}", id, status)));
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(depends_on.iter().map(i64::to_string).collect::<Vec<_>>().join(","))
+}
+
+// 试合并期间独占工作副本，避免与正在执行的任务互相干扰
+// 工作副本被占用时直接返回，不等待也不阻塞后续任务
+async fn preview(
+    repository: &Repository,
+    source_branch: &str,
+    new_task: &NewTask,
+    revisions: &str,
+) -> Result<MergePreview, (StatusCode, String)> {
+    let commit_message = commit_message(repository, source_branch, new_task, revisions).await?;
+    let revision_list: Vec<String> = revisions.split(',').map(str::to_string).collect();
+    let Some(_guard) = repository.workcopies.try_lock(&new_task.target_branch, "preview") else {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Working copy of {} is busy, try again later", new_task.target_branch),
+        ));
+    };
+    let preview = repository
+        .vcs
+        .preview_merge(&new_task.target_branch, source_branch, &revision_list)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to preview merge: {:#}", e)))?;
+    Ok(MergePreview { commit_message, ..preview })
 }
 
 pub async fn stop_task(
     Path(task_id): Path<i64>,
-    Extension(db_pool): Extension<SqlitePool>,
+    Extension(tx): Extension<UnboundedSender<TaskEvent>>,
 ) -> impl IntoResponse {
-    match sqlx::query("UPDATE tasks SET status = 'Stopped' WHERE id = ?")
-        .bind(task_id)
-        .execute(&db_pool)
+    match send_event(&tx, |reply| TaskEvent::Stop(task_id, reply)).await {
+        Ok(()) => (StatusCode::OK, "Task stopped").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// 调整排队中任务的位置
+pub async fn move_task(
+    Path(task_id): Path<i64>,
+    Extension(tx): Extension<UnboundedSender<TaskEvent>>,
+    Json(to): Json<QueueMove>,
+) -> impl IntoResponse {
+    match send_event(&tx, |reply| TaskEvent::Move(task_id, to, reply)).await {
+        Ok(()) => (StatusCode::OK, "Task moved").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// 按执行顺序列出等待中的任务
+pub async fn get_queue(Extension(db_pool): Extension<SqlitePool>) -> impl IntoResponse {
+    match sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE queue_position IS NOT NULL ORDER BY queue_position")
+        .fetch_all(&db_pool)
         .await
     {
-        Ok(_) => (StatusCode::OK, "Task stopped").into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to stop task: {}", e)).into_response(),
+        Ok(tasks) => Json(tasks).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch queue: {}", e)).into_response(),
+    }
+}
+
+// 手动重试未成功结束的任务
+pub async fn retry_task(
+    Path(task_id): Path<i64>,
+    Extension(tx): Extension<UnboundedSender<TaskEvent>>,
+) -> impl IntoResponse {
+    match send_event(&tx, |reply| TaskEvent::Retry(task_id, reply)).await {
+        Ok(()) => (StatusCode::OK, "Task requeued").into_response(),
+        Err(e) => e.into_response(),
     }
 }
 
 pub async fn delete_task(
     Path(task_id): Path<i64>,
+    Extension(tx): Extension<UnboundedSender<TaskEvent>>,
+) -> impl IntoResponse {
+    match send_event(&tx, |reply| TaskEvent::Delete(task_id, reply)).await {
+        Ok(()) => (StatusCode::OK, "Task deleted").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LogQuery {
+    since_seq: Option<i64>, // 只返回序号大于该值的日志
+}
+
+pub async fn get_logs(
+    Path(task_id): Path<i64>,
+    Query(query): Query<LogQuery>,
     Extension(db_pool): Extension<SqlitePool>,
 ) -> impl IntoResponse {
-    match sqlx::query("DELETE FROM tasks WHERE id = ?")
+    match fetch_logs(&db_pool, task_id, query.since_seq.unwrap_or(0)).await {
+        Ok(logs) => Json(logs).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch logs: {}", e)).into_response(),
+    }
+}
+
+async fn fetch_logs(db_pool: &SqlitePool, task_id: i64, since_seq: i64) -> sqlx::Result<Vec<TaskLog>> {
+    sqlx::query_as::<_, TaskLog>("SELECT * FROM task_logs WHERE task_id = ? AND seq > ? ORDER BY seq ASC")
         .bind(task_id)
-        .execute(&db_pool)
+        .bind(since_seq)
+        .fetch_all(db_pool)
+        .await
+}
+
+// 冲突结束的任务中阻止合并的路径
+pub async fn get_conflicts(
+    Path(task_id): Path<i64>,
+    Extension(db_pool): Extension<SqlitePool>,
+) -> impl IntoResponse {
+    match sqlx::query_as::<_, (i64,)>("SELECT id FROM tasks WHERE id = ?")
+        .bind(task_id)
+        .fetch_optional(&db_pool)
         .await
     {
-        Ok(_) => (StatusCode::OK, "Task deleted").into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete task: {}", e)).into_response(),
+        Ok(Some(_)) => {}
+        Ok(None) => return (StatusCode::NOT_FOUND, "Task not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch task: {}", e)).into_response(),
+    }
+    match sqlx::query_as::<_, Conflict>("SELECT path, kind FROM task_conflicts WHERE task_id = ? ORDER BY path, kind")
+        .bind(task_id)
+        .fetch_all(&db_pool)
+        .await
+    {
+        Ok(conflicts) => Json(conflicts).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch conflicts: {}", e)).into_response(),
     }
 }
 
-pub async fn get_logs(
+fn log_event(log: &TaskLog) -> Event {
+    Event::default()
+        .event("log")
+        .id(log.seq.to_string())
+        .json_data(log)
+        .unwrap_or_else(|_| Event::default().event("log"))
+}
+
+// 通过 SSE 推送任务日志：先回放已有日志，再推送新产生的日志，
+// 任务结束时发送 status 事件并关闭连接
+pub async fn stream_logs(
     Path(task_id): Path<i64>,
     Extension(db_pool): Extension<SqlitePool>,
+    Extension(log_tx): Extension<LogSender>,
 ) -> impl IntoResponse {
-    // 这里的逻辑取决于你如何存储日志信息
-    // 以下是一个示例，如果你有一个日志字段
-    let log = sqlx::query_as::<_, (String,)>("SELECT log FROM tasks WHERE id = ?")
+    // 先订阅再查询状态和回放，避免期间产生的日志或结束事件丢失
+    let mut receiver = log_tx.subscribe();
+    let status = match sqlx::query_as::<_, (TaskStatus,)>("SELECT status FROM tasks WHERE id = ?")
         .bind(task_id)
-        .fetch_one(&db_pool)
+        .fetch_optional(&db_pool)
         .await
-        .map(|(log,)| log)
-        .unwrap_or_else(|_| "No log found".to_string());
+    {
+        Ok(Some((status,))) => status,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Task not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch task: {}", e)).into_response(),
+    };
+
+    let stream = async_stream::stream! {
+        let mut last_seq = 0;
+        for log in fetch_logs(&db_pool, task_id, last_seq).await.unwrap_or_default() {
+            last_seq = log.seq;
+            yield Ok::<_, Infallible>(log_event(&log));
+        }
+
+        if status.is_terminal() {
+            yield Ok(Event::default().event("status").data(status.as_str()));
+            return;
+        }
+
+        loop {
+            match receiver.recv().await {
+                Ok(LogEvent::Line(log)) if log.task_id == task_id && log.seq > last_seq => {
+                    last_seq = log.seq;
+                    yield Ok(log_event(&log));
+                }
+                Ok(LogEvent::Finished { task_id: finished_id, status }) if finished_id == task_id => {
+                    yield Ok(Event::default().event("status").data(status.as_str()));
+                    break;
+                }
+                Ok(_) => {}
+                // 推送落后时从数据库补齐
+                Err(RecvError::Lagged(_)) => {
+                    for log in fetch_logs(&db_pool, task_id, last_seq).await.unwrap_or_default() {
+                        last_seq = log.seq;
+                        yield Ok(log_event(&log));
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+fn find_repository(repositories: &Repositories, name: &str) -> Result<Arc<Repository>, (StatusCode, String)> {
+    repositories
+        .get(name)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown repository {}", name)))
+}
 
-    (StatusCode::OK, log)
+pub async fn get_commands(Extension(commands): Extension<Arc<Commands>>) -> Json<Vec<CommandInfo>> {
+    Json(commands.info())
+}
+
+pub async fn get_repositories(Extension(repositories): Extension<Arc<Repositories>>) -> Json<Vec<RepositoryInfo>> {
+    Json(repositories.iter().map(|repository| repository.info()).collect())
+}
+
+#[derive(Deserialize)]
+pub struct BranchQuery {
+    #[serde(default)]
+    repository: String, // 版本库名称，为空时使用默认版本库
+    #[serde(default)]
+    refresh: bool, // 为 true 时忽略缓存重新查询
 }
 
 pub async fn get_branches(
-) -> Json<Vec<String>> {
-    // 这里需要与你的版本控制系统集成，以下是一个模拟示例
-    let branches = vec!["main".to_string(), "dev".to_string(), "feature-xyz".to_string()];
-    Json(branches)
+    Query(query): Query<BranchQuery>,
+    Extension(repositories): Extension<Arc<Repositories>>,
+) -> impl IntoResponse {
+    let repository = match find_repository(&repositories, &query.repository) {
+        Ok(repository) => repository,
+        Err(e) => return e.into_response(),
+    };
+    match repository.branch_cache.get_or_load(query.refresh, || repository.vcs.list_branches()).await {
+        Ok(branches) => Json(branches).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list branches: {:#}", e)).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CommitQuery {
+    #[serde(default)]
+    repository: String,              // 版本库名称，为空时使用默认版本库
+    limit: Option<u32>,              // 每页数量，默认 20，最多 100
+    before_revision: Option<String>, // 只返回早于该修订的提交
 }
 
 pub async fn get_commits(
     Path(branch_name): Path<String>,
-) -> Json<Vec<String>> {
-    // 这里需要与你的版本控制系统集成，以下是一个模拟示例
-    let commits = vec![
-        "commit1".to_string(),
-        "commit2".to_string(),
-        // ...更多提交
-    ];
-    Json(commits)
+    Query(query): Query<CommitQuery>,
+    Extension(repositories): Extension<Arc<Repositories>>,
+) -> impl IntoResponse {
+    let repository = match find_repository(&repositories, &query.repository) {
+        Ok(repository) => repository,
+        Err(e) => return e.into_response(),
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    match repository.vcs.list_commits(&branch_name, limit, query.before_revision.as_deref()).await {
+        Ok(commits) => Json(commits).into_response(),
+        Err(e) if e.downcast_ref::<InvalidRevisions>().is_some() => {
+            (StatusCode::BAD_REQUEST, format!("Failed to list commits: {:#}", e)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list commits: {:#}", e)).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EligibleQuery {
+    #[serde(default)]
+    repository: String,   // 版本库名称，为空时使用默认版本库
+    from: Option<String>, // 来源分支，默认为版本库配置的合并来源
+}
+
+// 来源分支上尚未合并到目标分支的提交
+pub async fn get_eligible(
+    Path(branch_name): Path<String>,
+    Query(query): Query<EligibleQuery>,
+    Extension(repositories): Extension<Arc<Repositories>>,
+) -> impl IntoResponse {
+    let repository = match find_repository(&repositories, &query.repository) {
+        Ok(repository) => repository,
+        Err(e) => return e.into_response(),
+    };
+    let source = query.from.unwrap_or_else(|| repository.merge_source.clone());
+    match repository.vcs.eligible_commits(&branch_name, &source).await {
+        Ok(commits) => Json(commits).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list eligible revisions: {:#}", e)).into_response(),
+    }
+}
+
+// 所有版本库保留的工作副本及其使用情况
+pub async fn get_workcopies(Extension(repositories): Extension<Arc<Repositories>>) -> Json<Vec<WorkcopyStatus>> {
+    let mut statuses = Vec::new();
+    for repository in repositories.iter() {
+        statuses.extend(repository.workcopies.status(&repository.name).await);
+    }
+    Json(statuses)
+}
+
+pub async fn get_schedules(Extension(db_pool): Extension<SqlitePool>) -> impl IntoResponse {
+    match sqlx::query_as::<_, Schedule>("SELECT * FROM schedules ORDER BY id")
+        .fetch_all(&db_pool)
+        .await
+    {
+        Ok(schedules) => Json(schedules).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch schedules: {}", e)).into_response(),
+    }
+}
+
+// 创建定时合并，下一次执行时间从当前时间算起
+pub async fn create_schedule(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(repositories): Extension<Arc<Repositories>>,
+    Json(new_schedule): Json<NewSchedule>,
+) -> impl IntoResponse {
+    let repository = match find_repository(&repositories, &new_schedule.repository) {
+        Ok(repository) => repository,
+        Err(e) => return e.into_response(),
+    };
+    if new_schedule.name.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "Schedule name is required".to_string()).into_response();
+    }
+    if new_schedule.target_branch.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Target branch is required".to_string()).into_response();
+    }
+    let source_branch = repository.source_branch(&new_schedule.source_branch).to_string();
+    for branch in [&source_branch, &new_schedule.target_branch] {
+        if let Err(e) = vcs::check_branch_name(branch) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+    if source_branch == new_schedule.target_branch {
+        return (StatusCode::BAD_REQUEST, "Source and target branch must differ".to_string()).into_response();
+    }
+    let next_run = match schedules::parse_cron(&new_schedule.cron) {
+        Ok(cron) => schedules::next_run(&cron),
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{:#}", e)).into_response(),
+    };
+
+    match sqlx::query_as::<_, Schedule>(
+        r#"
+        INSERT INTO schedules (created_at, name, cron, creator, repository, source_branch, target_branch, priority, missed, next_run)
+        VALUES (CURRENT_TIMESTAMP, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        RETURNING *
+        "#,
+    )
+    .bind(new_schedule.name.trim())
+    .bind(new_schedule.cron.trim())
+    .bind(new_schedule.creator)
+    .bind(&repository.name)
+    .bind(source_branch)
+    .bind(new_schedule.target_branch)
+    .bind(new_schedule.priority)
+    .bind(new_schedule.missed)
+    .bind(next_run)
+    .fetch_one(&db_pool)
+    .await
+    {
+        Ok(schedule) => (StatusCode::CREATED, Json(schedule)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create schedule: {}", e)).into_response(),
+    }
+}
+
+// 删除定时合并，已创建的任务不受影响
+pub async fn delete_schedule(
+    Path(schedule_id): Path<i64>,
+    Extension(db_pool): Extension<SqlitePool>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM schedules WHERE id = ?")
+        .bind(schedule_id)
+        .execute(&db_pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            (StatusCode::NOT_FOUND, format!("Schedule {} not found", schedule_id)).into_response()
+        }
+        Ok(_) => (StatusCode::OK, "Schedule deleted").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete schedule: {}", e)).into_response(),
+    }
 }
 
 async fn create_user_from_ip(ip: IpAddr) -> anyhow::Result<User>
@@ -0,0 +1,88 @@
+// src/logs.rs
+
+use models::{log::TaskLog, task::TaskStatus};
+use sqlx::SqlitePool;
+use tokio::sync::{broadcast, Mutex};
+
+// 实时推送给订阅者的日志事件
+#[derive(Clone, Debug)]
+pub enum LogEvent {
+    Line(TaskLog),
+    Finished { task_id: i64, status: TaskStatus }, // 任务结束时的最终状态
+}
+
+pub type LogSender = broadcast::Sender<LogEvent>;
+
+// 将任务输出逐行写入 task_logs 表，并广播给实时订阅者
+pub struct TaskLogger {
+    db_pool: SqlitePool,
+    events: LogSender,
+    task_id: i64,
+    attempt: i64, // 创建时任务的执行次数，日志按此区分各次执行
+    next_seq: Mutex<i64>, // 同时串行化写入，保证 seq 与写入顺序一致
+}
+
+impl TaskLogger {
+    // 序号从该任务已有日志之后继续编号
+    pub async fn new(db_pool: SqlitePool, events: LogSender, task_id: i64) -> Self {
+        let attempt = sqlx::query_as::<_, (i64,)>("SELECT attempt FROM tasks WHERE id = ?")
+            .bind(task_id)
+            .fetch_optional(&db_pool)
+            .await
+            .map(|attempt| attempt.map_or(0, |(attempt,)| attempt))
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to read attempt of task {}: {}", task_id, e);
+                0
+            });
+        let last_seq = sqlx::query_as::<_, (Option<i64>,)>("SELECT MAX(seq) FROM task_logs WHERE task_id = ?")
+            .bind(task_id)
+            .fetch_one(&db_pool)
+            .await
+            .map(|(seq,)| seq.unwrap_or(0))
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to read log sequence of task {}: {}", task_id, e);
+                0
+            });
+
+        TaskLogger {
+            db_pool,
+            events,
+            task_id,
+            attempt,
+            next_seq: Mutex::new(last_seq + 1),
+        }
+    }
+
+    // 追加一行日志，写入失败只打印错误，不影响任务执行
+    pub async fn append(&self, stream: &str, line: &str) {
+        let mut next_seq = self.next_seq.lock().await;
+        let result = sqlx::query_as::<_, TaskLog>(
+            r#"
+            INSERT INTO task_logs (task_id, seq, attempt, timestamp, stream, line)
+            VALUES (?1, ?2, ?3, strftime('%Y-%m-%d %H:%M:%f', 'now'), ?4, ?5)
+            RETURNING *
+            "#,
+        )
+        .bind(self.task_id)
+        .bind(*next_seq)
+        .bind(self.attempt)
+        .bind(stream)
+        .bind(line)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(log) => {
+                *next_seq += 1;
+                // 没有订阅者时发送失败，忽略即可
+                let _ = self.events.send(LogEvent::Line(log));
+            }
+            Err(e) => eprintln!("Failed to write log of task {}: {}", self.task_id, e),
+        }
+    }
+
+    // 记录处理器自身产生的信息
+    pub async fn system(&self, line: &str) {
+        self.append("system", line).await;
+    }
+}
@@ -2,45 +2,127 @@ use axum::{
     routing::{get, post},
     Router
 };
-use sqlx::sqlite::SqlitePoolOptions;
-use tokio::sync::mpsc::unbounded_channel;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc::unbounded_channel};
 use std::net::SocketAddr;
 
+mod cache;
+mod commands;
+mod config;
+mod executor;
 mod handlers;
+mod logs;
+mod message;
+mod schedules;
 mod tasks;
+mod vcs;
 
 // 启动服务
 #[tokio::main]
 async fn main() {
-    // 设置 SQLite 数据库连接池
+    // 读取配置文件，路径可通过 TASK_WEB_CONFIG 指定
+    let config_path = std::env::var("TASK_WEB_CONFIG").unwrap_or_else(|_| "config.toml".to_string());
+    let config = config::Config::load(&config_path).expect("Could not load the config file.");
+
+    // 设置 SQLite 数据库连接池，数据库文件不存在时自动创建
+    let connect_options = SqliteConnectOptions::from_str(&config.database_url)
+        .expect("Invalid database url.")
+        .create_if_missing(true);
     let db_pool = SqlitePoolOptions::new()
-        .connect("sqlite:task.db")
+        .connect_with(connect_options)
         .await
         .expect("Could not connect to the database.");
 
+    // 应用 migrations/ 下尚未执行的迁移
+    sqlx::migrate!()
+        .run(&db_pool)
+        .await
+        .expect("Could not run database migrations.");
+
+    // 版本库访问由处理器和接口共用
+    let repositories = Arc::new(vcs::Repositories::new(config.repositories.clone()));
+    let commands = Arc::new(commands::Commands::new(config.commands.clone()));
+
+    // 定期回收长时间未使用的工作副本
+    if config.workcopies.gc_interval_secs > 0 {
+        let repositories = repositories.clone();
+        let interval = Duration::from_secs(config.workcopies.gc_interval_secs);
+        let max_idle = Duration::from_secs(config.workcopies.max_idle_secs);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for repository in repositories.iter() {
+                    for name in repository.workcopies.collect_garbage(max_idle).await {
+                        println!("removed idle working copy {}/{}", repository.name, name);
+                    }
+                }
+            }
+        });
+    }
+
     // 创建一个无界任务通知的通道
     let (tx, rx) = unbounded_channel::<tasks::TaskEvent>();
 
+    // 创建日志实时推送的广播通道
+    let (log_tx, _) = broadcast::channel::<logs::LogEvent>(1024);
+
+    // 创建任务变化推送的广播通道
+    let (update_tx, _) = broadcast::channel::<models::event::TaskUpdate>(256);
+
     // 创建任务处理器
-    let mut task_processor = tasks::TaskProcessor::new(db_pool.clone(), rx).await;
+    let mut task_processor = tasks::TaskProcessor::new(
+        db_pool.clone(),
+        rx,
+        repositories.clone(),
+        commands.clone(),
+        config.processor.clone(),
+        log_tx.clone(),
+        update_tx.clone(),
+    )
+    .await;
 
     // 启动一个后台任务处理器
     tokio::spawn(async move {
         task_processor.run().await;
     });
 
+    // 定时合并到期时创建任务
+    tokio::spawn(schedules::run(db_pool.clone(), tx.clone(), repositories.clone()));
+
     // 构建我们的路由器
     let app = Router::new()
         .route("/api/tasks", get(handlers::get_tasks))
         .route("/api/tasks", post(handlers::create_task))
+        .route("/api/tasks/preview", post(handlers::preview_task))
+        .route("/api/tasks/commit_message", post(handlers::preview_commit_message))
         .route("/api/tasks/:task_id/stop", post(handlers::stop_task))
+        .route("/api/tasks/:task_id/retry", post(handlers::retry_task))
         .route("/api/tasks/:task_id/delete", post(handlers::delete_task))
+        .route("/api/tasks/:task_id/move", post(handlers::move_task))
+        .route("/api/queue", get(handlers::get_queue))
+        .route("/api/schedules", get(handlers::get_schedules))
+        .route("/api/schedules", post(handlers::create_schedule))
+        .route("/api/schedules/:schedule_id/delete", post(handlers::delete_schedule))
         .route("/api/tasks/:task_id/logs", get(handlers::get_logs))
+        .route("/api/tasks/:task_id/logs/stream", get(handlers::stream_logs))
+        .route("/api/tasks/:task_id/conflicts", get(handlers::get_conflicts))
+        .route("/api/repositories", get(handlers::get_repositories))
+        .route("/api/commands", get(handlers::get_commands))
         .route("/api/branches", get(handlers::get_branches))
         .route("/api/branches/:branch_name/commits", get(handlers::get_commits))
+        .route("/api/branches/:branch_name/eligible", get(handlers::get_eligible))
+        .route("/api/events", get(handlers::task_events))
         .route("/api/current_user", get(handlers::get_current_user))
+        .route("/api/admin/workcopies", get(handlers::get_workcopies))
         .layer(axum::Extension(db_pool))
         .layer(axum::Extension(tx))
+        .layer(axum::Extension(log_tx))
+        .layer(axum::Extension(update_tx))
+        .layer(axum::Extension(repositories))
+        .layer(axum::Extension(commands))
     ;
 
     // 运行我们的服务
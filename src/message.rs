@@ -0,0 +1,178 @@
+// src/message.rs
+
+use models::vcs::{Commit, VcsKind};
+
+use crate::vcs::Repository;
+
+// 提交信息模板中可用的占位符
+pub const PLACEHOLDERS: [&str; 7] = [
+    "revs",              // 规范化后的修订列表
+    "source",            // 来源分支
+    "target",            // 目标分支
+    "creator",           // 任务创建者
+    "repository",        // 版本库名称
+    "task_id",           // 任务 ID，预览时为空
+    "original_messages", // 被合并提交的原始提交信息，每个提交一段
+];
+
+pub const DEFAULT_TEMPLATE: &str = "Merged {revs} from {source} to {target} by {creator}";
+
+// 生成提交信息所需的任务信息
+pub struct MessageFields<'a> {
+    pub kind: VcsKind,
+    pub repository: &'a str,
+    pub source: &'a str,
+    pub target: &'a str,
+    pub creator: &'a str,
+    pub revisions: &'a str,
+    pub task_id: Option<i64>,
+}
+
+// 检查模板中的占位符都可识别，{{ 与 }} 分别表示字面的花括号
+pub fn validate_template(template: &str) -> anyhow::Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if rest[start..].starts_with("{{") {
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(end) = rest[start..].find('}') else {
+            anyhow::bail!("unclosed placeholder in commit message template");
+        };
+        let name = &rest[start + 1..start + end];
+        if !PLACEHOLDERS.contains(&name) {
+            anyhow::bail!("unknown placeholder {{{}}} in commit message template", name);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Ok(())
+}
+
+// 按版本库的模板生成提交信息，模板用到 {original_messages} 时读取被合并提交的原始信息
+pub async fn generate(repository: &Repository, fields: &MessageFields<'_>) -> anyhow::Result<String> {
+    let commits = if repository.commit_template.contains("{original_messages}") {
+        let revisions: Vec<String> = fields.revisions.split(',').map(str::to_string).collect();
+        repository.vcs.commits(fields.source, &revisions).await?
+    } else {
+        vec![]
+    };
+    Ok(render(&repository.commit_template, fields, &commits))
+}
+
+// 按模板生成提交信息，commits 为被合并的提交，用于 {original_messages}
+pub fn render(template: &str, fields: &MessageFields, commits: &[Commit]) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        message.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            message.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        let value = rest
+            .find('}')
+            .filter(|_| rest.starts_with('{'))
+            .and_then(|end| value_of(&rest[1..end], fields, commits).map(|value| (end, value)));
+        match value {
+            Some((end, value)) => {
+                message.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            // 无法识别的内容按原样输出
+            None => {
+                message.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message.trim_end().to_string()
+}
+
+fn value_of(name: &str, fields: &MessageFields, commits: &[Commit]) -> Option<String> {
+    let value = match name {
+        "revs" => fields.revisions.to_string(),
+        "source" => fields.source.to_string(),
+        "target" => fields.target.to_string(),
+        "creator" => fields.creator.to_string(),
+        "repository" => fields.repository.to_string(),
+        "task_id" => fields.task_id.map(|id| id.to_string()).unwrap_or_default(),
+        "original_messages" => original_messages(fields.kind, commits),
+        _ => return None,
+    };
+    Some(value)
+}
+
+// 每个提交一段：SVN 为 "r1234 author: 信息"，Git 使用短哈希
+fn original_messages(kind: VcsKind, commits: &[Commit]) -> String {
+    commits
+        .iter()
+        .map(|commit| {
+            let revision = match kind {
+                VcsKind::Svn => format!("r{}", commit.revision),
+                VcsKind::Git => commit.revision.chars().take(10).collect(),
+            };
+            format!("{} {}: {}", revision, commit.author, commit.message.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> MessageFields<'static> {
+        MessageFields {
+            kind: VcsKind::Svn,
+            repository: "project",
+            source: "trunk",
+            target: "release",
+            creator: "alice",
+            revisions: "1234-1235",
+            task_id: Some(7),
+        }
+    }
+
+    #[test]
+    fn render_fills_placeholders() {
+        let message = render("Merged {revs} from {source} to {target} by {creator} (#{task_id})", &fields(), &[]);
+        assert_eq!(message, "Merged 1234-1235 from trunk to release by alice (#7)");
+    }
+
+    #[test]
+    fn render_keeps_escaped_and_unknown_braces() {
+        assert_eq!(render("{{revs}} {unknown} {revs", &fields(), &[]), "{revs} {unknown} {revs");
+        assert_eq!(render("a }} b }", &fields(), &[]), "a } b }");
+    }
+
+    #[test]
+    fn render_lists_original_messages() {
+        let commits = [
+            Commit {
+                revision: "1234".to_string(),
+                author: "bob".to_string(),
+                message: "Fix crash\n".to_string(),
+                ..Default::default()
+            },
+            Commit {
+                revision: "1235".to_string(),
+                author: "carol".to_string(),
+                message: "Add test".to_string(),
+                ..Default::default()
+            },
+        ];
+        let message = render("{revs}\n\n{original_messages}\n", &fields(), &commits);
+        assert_eq!(message, "1234-1235\n\nr1234 bob: Fix crash\n\nr1235 carol: Add test");
+    }
+
+    #[test]
+    fn validate_template_checks_placeholders() {
+        assert!(validate_template(DEFAULT_TEMPLATE).is_ok());
+        assert!(validate_template("{{literal}} {task_id}").is_ok());
+        assert!(validate_template("{unknown}").is_err());
+        assert!(validate_template("Merged {revs").is_err());
+    }
+}
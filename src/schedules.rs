@@ -0,0 +1,203 @@
+// src/schedules.rs
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use models::schedule::{MissedRuns, Schedule};
+use models::task::{Task, TaskStatus};
+use sqlx::SqlitePool;
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+
+use crate::tasks::{self, TaskEvent};
+use crate::vcs::Repositories;
+
+// 检查定时合并是否到期的间隔
+const TICK: Duration = Duration::from_secs(30);
+
+// 到期超过该时间才处理的执行视为服务停止期间错过的执行
+const MISSED_AFTER: Duration = Duration::from_secs(300);
+
+// 与 SQLite CURRENT_TIMESTAMP 相同的时间格式，均为 UTC
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// 解析 cron 表达式，5 段写法补上秒字段
+pub fn parse_cron(expression: &str) -> anyhow::Result<cron::Schedule> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    cron::Schedule::from_str(&expression).map_err(|e| anyhow::anyhow!("invalid cron expression: {}", e))
+}
+
+// 当前时间之后的下一个执行时间，按服务器本地时间计算
+pub fn next_run(schedule: &cron::Schedule) -> Option<String> {
+    schedule
+        .after(&Local::now())
+        .next()
+        .map(|time| format_timestamp(time.with_timezone(&Utc)))
+}
+
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.format(TIMESTAMP_FORMAT).to_string()
+}
+
+// 解析数据库中保存的 UTC 时间
+pub fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+// 解析任务的 run_at：RFC 3339 格式，或不带时区、按服务器本地时间理解的时间
+pub fn parse_run_at(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+// 距离 run_at 的时间，已经过去或无法解析时为 None
+pub fn delay_until(run_at: &str) -> Option<Duration> {
+    let run_at = parse_timestamp(run_at)?;
+    (run_at - Utc::now()).to_std().ok().filter(|delay| !delay.is_zero())
+}
+
+// 定期检查到期的定时合并，为其创建任务并计算下一次执行时间
+pub async fn run(db_pool: SqlitePool, tx: UnboundedSender<TaskEvent>, repositories: Arc<Repositories>) {
+    loop {
+        let now = Utc::now();
+        let due = sqlx::query_as::<_, Schedule>(
+            "SELECT * FROM schedules WHERE enabled AND next_run IS NOT NULL AND next_run <= ? ORDER BY next_run",
+        )
+        .bind(format_timestamp(now))
+        .fetch_all(&db_pool)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to fetch due schedules: {}", e);
+            vec![]
+        });
+
+        for schedule in due {
+            let missed = schedule
+                .next_run
+                .as_deref()
+                .and_then(parse_timestamp)
+                .and_then(|due| (now - due).to_std().ok())
+                .is_some_and(|late| late > MISSED_AFTER);
+            // 错过多次也只补执行一次，下一次执行时间总是从当前时间算起
+            // 创建任务失败时记录一次错误，同样推进到下一次执行时间，不在每次检查时重试
+            let task_id = if missed && schedule.missed == MissedRuns::Skip {
+                println!("skipped missed run of schedule {} ({})", schedule.id, schedule.name);
+                None
+            } else {
+                match materialize(&db_pool, &tx, &repositories, &schedule).await {
+                    Ok(task_id) => task_id,
+                    Err(e) => {
+                        eprintln!("Failed to run schedule {} ({}): {:#}", schedule.id, schedule.name, e);
+                        None
+                    }
+                }
+            };
+
+            let next = parse_cron(&schedule.cron).ok().and_then(|cron| next_run(&cron));
+            if let Err(e) = sqlx::query(
+                "UPDATE schedules SET next_run = ?1, last_run = ?2, last_task_id = COALESCE(?3, last_task_id) WHERE id = ?4",
+            )
+            .bind(next)
+            .bind(format_timestamp(now))
+            .bind(task_id)
+            .bind(schedule.id)
+            .execute(&db_pool)
+            .await
+            {
+                eprintln!("Failed to update schedule {}: {}", schedule.id, e);
+            }
+        }
+
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+// 把来源分支上尚未合并的全部修订创建为任务
+// 没有可合并的修订，或上次创建的任务尚未结束时不创建
+async fn materialize(
+    db_pool: &SqlitePool,
+    tx: &UnboundedSender<TaskEvent>,
+    repositories: &Repositories,
+    schedule: &Schedule,
+) -> anyhow::Result<Option<i64>> {
+    let unfinished: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM tasks WHERE schedule_id = ? AND status IN (?, ?, ?, ?, ?, ?) LIMIT 1",
+    )
+    .bind(schedule.id)
+    .bind(TaskStatus::Pending)
+    .bind(TaskStatus::Scheduled)
+    .bind(TaskStatus::Blocked)
+    .bind(TaskStatus::Queued)
+    .bind(TaskStatus::Running)
+    .bind(TaskStatus::Retrying)
+    .fetch_optional(db_pool)
+    .await?;
+    if let Some((task_id,)) = unfinished {
+        println!("schedule {} ({}): task {} has not finished yet", schedule.id, schedule.name, task_id);
+        return Ok(None);
+    }
+
+    let repository = repositories
+        .get(&schedule.repository)
+        .ok_or_else(|| anyhow::anyhow!("unknown repository {}", schedule.repository))?;
+    let source_branch = repository.source_branch(&schedule.source_branch);
+    let vcs = &repository.vcs;
+
+    // eligible_commits 从新到旧排列
+    let commits = vcs.eligible_commits(&schedule.target_branch, source_branch).await?;
+    if commits.is_empty() {
+        println!("schedule {} ({}): nothing to merge", schedule.id, schedule.name);
+        return Ok(None);
+    }
+    let revisions: Vec<String> = commits.into_iter().rev().map(|commit| commit.revision).collect();
+    let revisions = vcs.normalize_revisions(source_branch, &revisions.join(",")).await?;
+
+    let task: Task = sqlx::query_as(
+        r#"
+        INSERT INTO tasks (created_at, creator, repository, source_branch, target_branch, revisions, priority, schedule_id, status)
+        VALUES (CURRENT_TIMESTAMP, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        RETURNING *
+        "#,
+    )
+    .bind(&schedule.creator)
+    .bind(&repository.name)
+    .bind(source_branch)
+    .bind(&schedule.target_branch)
+    .bind(revisions)
+    .bind(schedule.priority)
+    .bind(schedule.id)
+    .bind(TaskStatus::Pending)
+    .fetch_one(db_pool)
+    .await?;
+
+    // 未被处理器接收时删除刚创建的记录，下次检查时重新创建
+    let (reply_tx, reply_rx) = oneshot::channel();
+    let accepted = match tx.send(TaskEvent::Create(Box::new(task.clone()), reply_tx)) {
+        Ok(()) => match reply_rx.await {
+            Ok(result) => result.map_err(|rejection| anyhow::anyhow!("{}", rejection)),
+            Err(_) => Err(anyhow::anyhow!("task processor dropped the request")),
+        },
+        Err(_) => Err(anyhow::anyhow!("task processor is not running")),
+    };
+    if let Err(e) = accepted {
+        tasks::discard_pending(db_pool, task.id).await;
+        return Err(e);
+    }
+    println!("schedule {} ({}) created task {}", schedule.id, schedule.name, task.id);
+    Ok(Some(task.id))
+}
@@ -1,30 +1,135 @@
 // src/tasks.rs
 
-use models::task::Task;
+use models::{event::TaskUpdate, task::{FailureClass, QueueMove, Task, TaskKind, TaskStatus}, vcs::Conflict};
 use sqlx::SqlitePool;
-use tokio::sync::mpsc::UnboundedReceiver;
-use std::collections::VecDeque;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::commands::Commands;
+use crate::config::{ProcessorConfig, RecoveryPolicy, RetryConfig};
+use crate::executor::{self, Outcome};
+use crate::logs::{LogEvent, LogSender, TaskLogger};
+use crate::schedules;
+use crate::vcs::{Repositories, Repository};
+
+// 处理器拒绝事件的原因
+#[derive(Debug)]
+pub enum Rejection {
+    NotFound(i64),
+    Conflict(String), // 当前状态不允许该操作
+    Internal(String),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::NotFound(task_id) => write!(f, "Task {} not found", task_id),
+            Rejection::Conflict(reason) | Rejection::Internal(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl From<sqlx::Error> for Rejection {
+    fn from(e: sqlx::Error) -> Self {
+        Rejection::Internal(format!("Database error: {}", e))
+    }
+}
+
+// 处理器对事件的答复
+pub type Reply = oneshot::Sender<Result<(), Rejection>>;
 
 // 定义表示不同任务事件的枚举
 pub enum TaskEvent {
-    Create(Task),
-    Stop(i64),
-    Delete(i64),
+    Create(Box<Task>, Reply),
+    Stop(i64, Reply),
+    Delete(i64, Reply),
+    Move(i64, QueueMove, Reply),
+    Retry(i64, Reply),
+}
+
+pub type UpdateSender = broadcast::Sender<TaskUpdate>;
+
+// 删除处理器未能接收的新任务，避免它在服务重启后被当作待处理任务执行
+// 只删除仍为 Pending 的任务，已被处理器接收的任务不受影响
+pub async fn discard_pending(db_pool: &SqlitePool, task_id: i64) {
+    if let Err(e) = sqlx::query("DELETE FROM tasks WHERE id = ? AND status = ?")
+        .bind(task_id)
+        .bind(TaskStatus::Pending)
+        .execute(db_pool)
+        .await
+    {
+        eprintln!("Failed to discard task {}: {}", task_id, e);
+    }
+}
+
+// 后台执行完成后回传的结果：任务 ID、执行结果与执行耗时
+type TaskResult = (i64, anyhow::Result<Outcome>, Duration);
+
+// 被阻塞任务的前置任务状态
+enum DependencyState {
+    Ready,          // 前置任务均已成功
+    Waiting,        // 仍有前置任务未结束
+    Broken(String), // 有前置任务未成功结束或已被删除，附带原因
+}
+
+// 正在执行的任务及其后台句柄
+struct RunningTask {
+    task: Task,
+    handle: JoinHandle<()>,
 }
 
 // 任务处理器结构体
 pub struct TaskProcessor {
     db_pool: SqlitePool,
-    queue: VecDeque<Task>, // 使用 VecDeque 实现任务队列
+    queue: VecDeque<Task>, // 等待执行的任务，按执行顺序排列
+    blocked: Vec<Task>, // 等待前置任务成功的任务
     receiver: UnboundedReceiver<TaskEvent>, // 用于接收任务事件的通道接收器
+    repositories: Arc<Repositories>,
+    commands: Arc<Commands>,
+    config: ProcessorConfig,
+    log_tx: LogSender, // 日志实时推送通道
+    update_tx: UpdateSender, // 任务变化推送通道
+    running: HashMap<i64, RunningTask>, // 正在执行的任务，按任务 ID 索引
+    timers: HashMap<i64, JoinHandle<()>>, // 等待自动重试或定时执行的任务及其计时器
+    done_tx: UnboundedSender<TaskResult>,
+    done_rx: UnboundedReceiver<TaskResult>,
+    timer_tx: UnboundedSender<i64>, // 计时结束时发送任务 ID
+    timer_rx: UnboundedReceiver<i64>,
 }
 
 impl TaskProcessor {
-    pub async fn new(db_pool: SqlitePool, receiver: UnboundedReceiver<TaskEvent>) -> Self {
+    pub async fn new(
+        db_pool: SqlitePool,
+        receiver: UnboundedReceiver<TaskEvent>,
+        repositories: Arc<Repositories>,
+        commands: Arc<Commands>,
+        config: ProcessorConfig,
+        log_tx: LogSender,
+        update_tx: UpdateSender,
+    ) -> Self {
+        let (done_tx, done_rx) = unbounded_channel();
+        let (timer_tx, timer_rx) = unbounded_channel();
         let mut processor = TaskProcessor {
             db_pool,
             queue: VecDeque::new(),
+            blocked: Vec::new(),
             receiver,
+            repositories,
+            commands,
+            config,
+            log_tx,
+            update_tx,
+            running: HashMap::new(),
+            timers: HashMap::new(),
+            done_tx,
+            done_rx,
+            timer_tx,
+            timer_rx,
         };
 
         // 初始化时从数据库加载任务
@@ -34,51 +139,741 @@ impl TaskProcessor {
     }
 
     async fn load_tasks(&mut self) {
-        let tasks = sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE status NOT IN ('Stopped', 'Cancelled') ORDER BY created_at ASC")
-            .fetch_all(&self.db_pool)
-            .await
-            .expect("Failed to fetch tasks");
+        // 按上次保存的队列顺序恢复，未排过队的任务按优先级和创建时间排在后面
+        let tasks = sqlx::query_as::<_, Task>(
+            "SELECT * FROM tasks WHERE status IN (?, ?, ?, ?, ?, ?) \
+             ORDER BY queue_position IS NULL, queue_position, priority DESC, created_at ASC",
+        )
+        .bind(TaskStatus::Pending)
+        .bind(TaskStatus::Scheduled)
+        .bind(TaskStatus::Blocked)
+        .bind(TaskStatus::Queued)
+        .bind(TaskStatus::Running)
+        .bind(TaskStatus::Retrying)
+        .fetch_all(&self.db_pool)
+        .await
+        .expect("Failed to fetch tasks");
 
         for task in tasks {
-            if task.status == "Running" {
-                // 对于运行中的任务，执行特别的动作
-                self.handle_running_task(&task).await;
-            } else {
-                // 未开始的任务放入队列
-                self.queue.push_back(task);
+            match task.status {
+                TaskStatus::Running => {
+                    // 对于运行中的任务，执行特别的动作
+                    self.handle_running_task(&task).await;
+                }
+                TaskStatus::Pending => {
+                    // 尚未被接收的任务按新任务处理，修订列表不合法的标记为 Failed
+                    let task = match self.check_revisions(&task).await {
+                        Ok(task) => task,
+                        Err(reason) => {
+                            self.reject_pending(&task, &reason).await;
+                            continue;
+                        }
+                    };
+                    if let Err(e) = self.accept(&task).await {
+                        eprintln!("Failed to queue task {}: {}", task.id, e);
+                    }
+                }
+                TaskStatus::Blocked => {
+                    // 前置任务的状态在 resolve_blocked 中重新检查
+                    self.blocked.push(task);
+                }
+                TaskStatus::Retrying => {
+                    // 重启期间已到时间的任务立即重新排队
+                    let delay = task.retry_at.map_or(0, |retry_at| retry_at - unix_now()).max(0);
+                    self.start_timer(task.id, Duration::from_secs(delay as u64));
+                }
+                TaskStatus::Scheduled => {
+                    let delay = task.run_at.as_deref().and_then(schedules::delay_until).unwrap_or_default();
+                    self.start_timer(task.id, delay);
+                }
+                _ => {
+                    // 未开始的任务放入队列
+                    self.queue.push_back(task);
+                }
             }
         }
     }
 
-    async fn handle_running_task(&self, task: &Task) {
-        // 实现特别的动作，比如可能需要检查任务状态或启动任务执行的逻辑
+    // 旧版本写入的 Pending 任务未经创建时的校验，按同样的规则检查修订列表并保存规范化后的结果
+    // 无法完成校验时同样不执行，避免未经校验的修订被合并
+    async fn check_revisions(&self, task: &Task) -> Result<Task, String> {
+        if task.kind != TaskKind::Merge {
+            return Ok(task.clone());
+        }
+        let Some(repository) = self.repository_of(task) else {
+            return Err(format!("unknown repository {}", task.repository));
+        };
+        let source_branch = repository.source_branch(&task.source_branch);
+        let revisions = repository
+            .vcs
+            .normalize_revisions(source_branch, &task.revisions)
+            .await
+            .map_err(|e| format!("invalid revisions: {:#}", e))?;
+        if revisions == task.revisions {
+            return Ok(task.clone());
+        }
+        sqlx::query_as::<_, Task>("UPDATE tasks SET revisions = ? WHERE id = ? RETURNING *")
+            .bind(revisions)
+            .bind(task.id)
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| format!("failed to save revisions: {}", e))
+    }
+
+    // 未通过校验的任务直接标记为 Failed，不进入队列
+    async fn reject_pending(&self, task: &Task, reason: &str) {
+        let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task.id).await;
+        log.system(&format!("Task rejected: {}", reason)).await;
+        match self.set_status(task.id, TaskStatus::Failed).await {
+            Ok(_) => self.notify_finished(task.id, TaskStatus::Failed),
+            Err(e) => eprintln!("Failed to mark task {} as failed: {}", task.id, e),
+        }
+    }
+
+    // 服务重启时仍处于 Running 的任务：合并进程已随服务退出，
+    // 先清理工作副本中残留的合并结果，再按配置标记为 Interrupted 或重新排队
+    async fn handle_running_task(&mut self, task: &Task) {
+        let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task.id).await;
+        log.system("Server restarted while the task was running").await;
+
+        if let Some(repository) = self.repository_of(task) {
+            let workcopy = repository.vcs.workcopy_path(&task.target_branch);
+            if workcopy.exists() {
+                match repository.vcs.has_changes(&workcopy).await {
+                    Ok(false) => log.system("Working copy is clean").await,
+                    Ok(true) => log.system("Working copy contains changes left by the interrupted merge").await,
+                    Err(e) => log.system(&format!("Failed to check working copy: {:#}", e)).await,
+                }
+                self.revert_workcopy(&repository, task, &log).await;
+            }
+        }
+
+        let next = match self.config.recovery {
+            RecoveryPolicy::Interrupt => TaskStatus::Interrupted,
+            RecoveryPolicy::Requeue => TaskStatus::Queued,
+        };
+        match self.set_status(task.id, next).await {
+            Ok(task) if next == TaskStatus::Queued => {
+                log.system("Task requeued after restart").await;
+                self.queue.push_back(task);
+            }
+            Ok(_) => {
+                log.system("Task marked as Interrupted").await;
+                self.notify_finished(task.id, next);
+            }
+            Err(e) => eprintln!("Failed to recover task {}: {}", task.id, e),
+        }
     }
 
-    // 处理接收到的事件
+    // 处理接收到的事件，并在有空闲名额时执行队列中可以开始的任务
     pub async fn run(&mut self) {
-        while let Some(event) = self.receiver.recv().await {
-            match event {
-                TaskEvent::Create(task) => {
-                    // 处理创建任务事件
-                    self.queue.push_back(task);
+        loop {
+            self.resolve_blocked().await;
+            self.start_ready().await;
+            self.persist_queue().await;
+
+            tokio::select! {
+                event = self.receiver.recv() => match event {
+                    Some(TaskEvent::Create(task, reply)) => {
+                        // 处理创建任务事件，未被接收的任务会被删除，接收后才推送给客户端
+                        let accepted = self.accept(&task).await;
+                        if accepted.is_ok() {
+                            self.publish(TaskUpdate::Created(*task));
+                        }
+                        let _ = reply.send(accepted);
+                    },
+                    Some(TaskEvent::Stop(task_id, reply)) => {
+                        // 处理停止任务事件
+                        let _ = reply.send(self.stop_task(task_id).await);
+                    },
+                    Some(TaskEvent::Delete(task_id, reply)) => {
+                        // 处理删除任务事件
+                        let _ = reply.send(self.delete_task(task_id).await);
+                    },
+                    Some(TaskEvent::Move(task_id, to, reply)) => {
+                        let _ = reply.send(self.move_task(task_id, to));
+                    },
+                    Some(TaskEvent::Retry(task_id, reply)) => {
+                        let _ = reply.send(self.retry_task(task_id).await);
+                    },
+                    None => break,
                 },
-                TaskEvent::Stop(task_id) => {
-                    // 处理停止任务事件
-                    self.stop_task(task_id).await;
+                Some((task_id, result, elapsed)) = self.done_rx.recv() => {
+                    self.finish_task(task_id, result, elapsed).await;
                 },
-                TaskEvent::Delete(task_id) => {
-                    // 处理删除任务事件
-                    self.delete_task(task_id).await;
+                Some(task_id) = self.timer_rx.recv() => {
+                    self.wake(task_id).await;
                 },
             }
         }
     }
 
-    async fn stop_task(&self, task_id: i64) {
-        // 实现停止任务的逻辑
+    // 接收新任务：指定了 run_at 且尚未到时间的任务先进入 Scheduled，到时间后再按 release 处理
+    async fn accept(&mut self, task: &Task) -> Result<(), Rejection> {
+        if let Some(delay) = task.run_at.as_deref().and_then(schedules::delay_until) {
+            self.set_status(task.id, TaskStatus::Scheduled).await?;
+            self.start_timer(task.id, delay);
+            return Ok(());
+        }
+        self.release(task).await
+    }
+
+    // 有前置任务时先进入 Blocked，由 resolve_blocked 决定何时排队
+    async fn release(&mut self, task: &Task) -> Result<(), Rejection> {
+        if task.dependencies().is_empty() {
+            let task = self.set_status(task.id, TaskStatus::Queued).await?;
+            self.enqueue(task);
+        } else {
+            let task = self.set_status(task.id, TaskStatus::Blocked).await?;
+            self.blocked.push(task);
+        }
+        Ok(())
+    }
+
+    // 检查被阻塞的任务：前置任务全部成功时排队，任一前置任务未成功结束或已被删除时取消
+    // 被取消的任务可能又是其他任务的前置任务，因此重复检查直到没有变化
+    async fn resolve_blocked(&mut self) {
+        loop {
+            let mut cancelled = false;
+            let mut index = 0;
+            while index < self.blocked.len() {
+                match self.dependency_state(&self.blocked[index]).await {
+                    DependencyState::Waiting => index += 1,
+                    DependencyState::Ready => {
+                        let task = self.blocked.remove(index);
+                        match self.set_status(task.id, TaskStatus::Queued).await {
+                            Ok(task) => self.enqueue(task),
+                            Err(e) => eprintln!("Failed to queue task {}: {}", task.id, e),
+                        }
+                    }
+                    DependencyState::Broken(reason) => {
+                        let task = self.blocked.remove(index);
+                        let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task.id).await;
+                        log.system(&format!("Task cancelled: {}", reason)).await;
+                        match self.set_status(task.id, TaskStatus::Cancelled).await {
+                            Ok(_) => self.notify_finished(task.id, TaskStatus::Cancelled),
+                            Err(e) => eprintln!("Failed to cancel task {}: {}", task.id, e),
+                        }
+                        cancelled = true;
+                    }
+                }
+            }
+            if !cancelled {
+                break;
+            }
+        }
+    }
+
+    async fn dependency_state(&self, task: &Task) -> DependencyState {
+        let mut state = DependencyState::Ready;
+        for dependency in task.dependencies() {
+            match self.current_status(dependency).await {
+                Ok(TaskStatus::Succeeded) => {}
+                Ok(status) if status.is_terminal() => {
+                    return DependencyState::Broken(format!("prerequisite task {} is {}", dependency, status));
+                }
+                Ok(_) => state = DependencyState::Waiting,
+                Err(Rejection::NotFound(_)) => {
+                    return DependencyState::Broken(format!("prerequisite task {} was deleted", dependency));
+                }
+                Err(e) => {
+                    eprintln!("Failed to check prerequisites of task {}: {}", task.id, e);
+                    state = DependencyState::Waiting;
+                }
+            }
+        }
+        state
+    }
+
+    // 按优先级插入队列：排在所有优先级不低于它的任务之后
+    fn enqueue(&mut self, task: Task) {
+        let index = self
+            .queue
+            .iter()
+            .position(|queued| queued.priority < task.priority)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(index, task);
+    }
+
+    // 把排队中的任务移到队首、队尾或指定位置
+    fn move_task(&mut self, task_id: i64, to: QueueMove) -> Result<(), Rejection> {
+        let Some(index) = self.queue.iter().position(|task| task.id == task_id) else {
+            return Err(Rejection::Conflict(format!("Task {} is not waiting in the queue", task_id)));
+        };
+        let task = self.queue.remove(index).unwrap();
+        let index = match to {
+            QueueMove::Front => 0,
+            QueueMove::Back => self.queue.len(),
+            QueueMove::Position(position) => position.saturating_sub(1).min(self.queue.len()),
+        };
+        self.queue.insert(index, task);
+        Ok(())
+    }
+
+    // 把队列顺序写回数据库，只更新位置有变化的任务
+    async fn persist_queue(&mut self) {
+        for index in 0..self.queue.len() {
+            let position = index as i64 + 1;
+            if self.queue[index].queue_position == Some(position) {
+                continue;
+            }
+            match sqlx::query_as::<_, Task>("UPDATE tasks SET queue_position = ? WHERE id = ? RETURNING *")
+                .bind(position)
+                .bind(self.queue[index].id)
+                .fetch_one(&self.db_pool)
+                .await
+            {
+                Ok(task) => {
+                    self.publish(TaskUpdate::StatusChanged(task.clone()));
+                    self.queue[index] = task;
+                }
+                Err(e) => eprintln!("Failed to save queue position of task {}: {}", self.queue[index].id, e),
+            }
+        }
+    }
+
+    // 合并任务所在的版本库，命令任务不使用版本库
+    fn repository_of(&self, task: &Task) -> Option<Arc<Repository>> {
+        match task.kind {
+            TaskKind::Merge => self.repositories.get(&task.repository),
+            TaskKind::Command => None,
+        }
+    }
+
+    // 任务所在的版本库与目标分支，相同的任务不能同时执行
+    // 命令任务以脚本名称代替，同一脚本同时只运行一个
+    fn branch_key(&self, task: &Task) -> (String, String) {
+        if task.kind == TaskKind::Command {
+            return (String::new(), task.command.clone());
+        }
+        let repository = match self.repositories.get(&task.repository) {
+            Some(repository) => repository.name.clone(),
+            None => task.repository.clone(),
+        };
+        (repository, task.target_branch.clone())
+    }
+
+    // 按队列顺序启动任务，直到达到并发上限
+    // 目标分支上已有任务在执行或排在前面的任务未能开始时，后面的同分支任务继续等待，
+    // 保证同一分支的任务严格按队列顺序执行
+    async fn start_ready(&mut self) {
+        let max_parallel = self.config.max_parallel.max(1);
+        let mut busy: HashSet<(String, String)> =
+            self.running.values().map(|running| self.branch_key(&running.task)).collect();
+        let mut index = 0;
+        while index < self.queue.len() && self.running.len() < max_parallel {
+            let key = self.branch_key(&self.queue[index]);
+            if busy.contains(&key) {
+                index += 1;
+                continue;
+            }
+            busy.insert(key);
+            let task = self.queue.remove(index).unwrap();
+            self.start_task(task).await;
+        }
+    }
+
+    // 标记任务为 Running 并在后台执行
+    async fn start_task(&mut self, task: Task) {
+        let task = match self.start_attempt(task.id).await {
+            Ok(task) => task,
+            Err(e) => {
+                eprintln!("Failed to mark task {} as running: {}", task.id, e);
+                return;
+            }
+        };
+
+        let repository = self.repository_of(&task);
+        let command = self.commands.get(&task.command);
+        let done_tx = self.done_tx.clone();
+        let running_task = task.clone();
+        let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task.id).await;
+        // 任务单独指定的超时优先于全局配置，0 表示不限制
+        let timeout_secs = task.timeout_secs.map_or(self.config.timeout_secs, |secs| secs.max(0) as u64);
+        let timeout = (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs));
+        let handle = tokio::spawn(async move {
+            let started = Instant::now();
+            if running_task.attempt > 1 {
+                log.system(&format!("Attempt {}", running_task.attempt)).await;
+            }
+            // 合并任务的耗时由 execute 从取得工作副本后开始计算
+            let (result, elapsed) = match (running_task.kind, repository, command) {
+                (TaskKind::Merge, Some(repository), _) => executor::execute(&repository, &running_task, timeout, &log).await,
+                (TaskKind::Merge, None, _) => {
+                    (Err(anyhow::anyhow!("Unknown repository {}", running_task.repository)), started.elapsed())
+                }
+                (TaskKind::Command, _, Some(command)) => {
+                    (executor::run_script(&command, &running_task, timeout, &log).await, started.elapsed())
+                }
+                (TaskKind::Command, _, None) => {
+                    (Err(anyhow::anyhow!("Unknown command {}", running_task.command)), started.elapsed())
+                }
+            };
+            if let Err(e) = &result {
+                log.system(&format!("Task aborted: {:#}", e)).await;
+            }
+            let _ = done_tx.send((running_task.id, result, elapsed));
+        });
+        self.running.insert(task.id, RunningTask { task, handle });
+    }
+
+    // 转为 Running 并增加执行次数
+    async fn start_attempt(&self, task_id: i64) -> Result<Task, Rejection> {
+        self.check_transition(task_id, TaskStatus::Running).await?;
+        let task = sqlx::query_as::<_, Task>(
+            "UPDATE tasks SET status = ?1, attempt = attempt + 1, queue_position = NULL, retry_at = NULL \
+             WHERE id = ?2 RETURNING *",
+        )
+        .bind(TaskStatus::Running)
+        .bind(task_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+        self.publish(TaskUpdate::StatusChanged(task.clone()));
+        Ok(task)
+    }
+
+    fn is_running(&self, task_id: i64) -> bool {
+        self.running.contains_key(&task_id)
+    }
+
+    // 记录执行结果，冲突的路径另存到 task_conflicts
+    async fn finish_task(&mut self, task_id: i64, result: anyhow::Result<Outcome>, elapsed: Duration) {
+        // 已被停止的任务可能仍有结果在通道中，忽略即可
+        let Some(running) = self.running.remove(&task_id) else {
+            return;
+        };
+
+        let elapsed_secs = elapsed.as_secs() as i64;
+        let (status, exit_code, class) = match result {
+            Ok(Outcome::Succeeded) => (TaskStatus::Succeeded, Some(0), None),
+            Ok(Outcome::Failed(code, class)) => (TaskStatus::Failed, Some(code), Some(class)),
+            Ok(Outcome::Conflicted(conflicts)) => {
+                if let Err(e) = self.save_conflicts(task_id, &conflicts).await {
+                    eprintln!("Failed to save conflicts of task {}: {}", task_id, e);
+                }
+                (TaskStatus::Conflicted, None, Some(FailureClass::Conflict))
+            }
+            Ok(Outcome::TimedOut) => (TaskStatus::TimedOut, None, Some(FailureClass::Timeout)),
+            Err(e) => {
+                eprintln!("Task {} failed: {}", task_id, e);
+                (TaskStatus::Failed, None, Some(FailureClass::Error))
+            }
+        };
+
+        if let Some(class) = class.filter(|class| self.should_retry(&running.task, *class)) {
+            self.retry_later(&running.task, exit_code, elapsed_secs, class).await;
+            return;
+        }
+
+        if let Err(e) = self.check_transition(task_id, status).await {
+            eprintln!("Failed to record result of task {}: {}", task_id, e);
+            return;
+        }
+        match sqlx::query_as::<_, Task>(
+            "UPDATE tasks SET status = ?1, exit_code = ?2, elapsed_secs = ?3, finished_at = CURRENT_TIMESTAMP WHERE id = ?4 RETURNING *",
+        )
+        .bind(status)
+        .bind(exit_code)
+        .bind(elapsed_secs)
+        .bind(task_id)
+        .fetch_optional(&self.db_pool)
+        .await
+        {
+            Ok(Some(task)) => self.publish(TaskUpdate::StatusChanged(task)),
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to record result of task {}: {}", task_id, e),
+        }
+        self.notify_finished(task_id, status);
+    }
+
+    // 任务的重试配置：单独指定的部分覆盖全局配置
+    fn retry_config(&self, task: &Task) -> RetryConfig {
+        let mut retry = self.config.retry.clone();
+        if let Some(max_attempts) = task.max_attempts {
+            retry.max_attempts = max_attempts.clamp(0, i64::from(u32::MAX)) as u32;
+        }
+        if let Some(backoff_secs) = task.backoff_secs {
+            retry.backoff_secs = backoff_secs.max(0) as u64;
+        }
+        match task.retry_on.as_deref().map(serde_json::from_str) {
+            Some(Ok(retry_on)) => retry.retry_on = retry_on,
+            Some(Err(e)) => eprintln!("Invalid retry_on of task {}: {}", task.id, e),
+            None => {}
+        }
+        retry
+    }
+
+    // 最近一次手动重试以来的执行次数
+    fn attempts_since_retry(task: &Task) -> i64 {
+        task.attempt - task.attempts_before_retry
+    }
+
+    // 按最大执行次数及可重试的失败类型判断是否自动重试
+    fn should_retry(&self, task: &Task, class: FailureClass) -> bool {
+        let retry = self.retry_config(task);
+        retry.retry_on.contains(&class) && Self::attempts_since_retry(task) < i64::from(retry.max_attempts)
+    }
+
+    // 记录本次执行的退出码，退避时间结束后重新排队
+    async fn retry_later(&mut self, task: &Task, exit_code: Option<i32>, elapsed_secs: i64, class: FailureClass) {
+        let delay = self.retry_config(task).backoff(Self::attempts_since_retry(task));
+        if let Err(e) = self.check_transition(task.id, TaskStatus::Retrying).await {
+            eprintln!("Failed to schedule retry of task {}: {}", task.id, e);
+            return;
+        }
+        match sqlx::query_as::<_, Task>(
+            "UPDATE tasks SET status = ?1, exit_code = ?2, elapsed_secs = ?3, retry_at = ?4 WHERE id = ?5 RETURNING *",
+        )
+        .bind(TaskStatus::Retrying)
+        .bind(exit_code)
+        .bind(elapsed_secs)
+        .bind(unix_now() + delay.as_secs() as i64)
+        .bind(task.id)
+        .fetch_one(&self.db_pool)
+        .await
+        {
+            Ok(task) => self.publish(TaskUpdate::StatusChanged(task)),
+            Err(e) => {
+                eprintln!("Failed to schedule retry of task {}: {}", task.id, e);
+                return;
+            }
+        }
+        let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task.id).await;
+        log.system(&format!("Attempt {} failed ({:?}), retrying in {} seconds", task.attempt, class, delay.as_secs())).await;
+        self.start_timer(task.id, delay);
+    }
+
+    fn start_timer(&mut self, task_id: i64, delay: Duration) {
+        let timer_tx = self.timer_tx.clone();
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = timer_tx.send(task_id);
+        });
+        self.timers.insert(task_id, handle);
+    }
+
+    // 计时结束：等待重试的任务重新排队，定时执行的任务按 release 处理
+    // 期间已被取消或删除的任务不再处理
+    async fn wake(&mut self, task_id: i64) {
+        if self.timers.remove(&task_id).is_none() {
+            return;
+        }
+        let task = match sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE id = ?")
+            .bind(task_id)
+            .fetch_one(&self.db_pool)
+            .await
+        {
+            Ok(task) => task,
+            Err(e) => {
+                eprintln!("Failed to fetch task {}: {}", task_id, e);
+                return;
+            }
+        };
+        let result = match task.status {
+            TaskStatus::Scheduled => self.release(&task).await,
+            _ => self.set_status(task_id, TaskStatus::Queued).await.map(|task| self.enqueue(task)),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to queue task {}: {}", task_id, e);
+        }
+    }
+
+    // 手动重试未成功结束的任务：回到 Pending 后按新任务处理，之前各次执行的日志保留
+    // 自动重试的次数从这次手动重试开始重新计算
+    async fn retry_task(&mut self, task_id: i64) -> Result<(), Rejection> {
+        let status = self.current_status(task_id).await?;
+        if !status.can_retry() {
+            return Err(Rejection::Conflict(format!("Task {} is {}, only unsuccessful tasks can be retried", task_id, status)));
+        }
+        sqlx::query("DELETE FROM task_conflicts WHERE task_id = ?")
+            .bind(task_id)
+            .execute(&self.db_pool)
+            .await?;
+        let task = sqlx::query_as::<_, Task>(
+            "UPDATE tasks SET status = ?1, exit_code = NULL, finished_at = NULL, retry_at = NULL, attempts_before_retry = attempt \
+             WHERE id = ?2 RETURNING *",
+        )
+        .bind(TaskStatus::Pending)
+        .bind(task_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+        let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task_id).await;
+        log.system("Retry requested").await;
+        self.accept(&task).await
+    }
+
+    async fn save_conflicts(&self, task_id: i64, conflicts: &[Conflict]) -> sqlx::Result<()> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("DELETE FROM task_conflicts WHERE task_id = ?")
+            .bind(task_id)
+            .execute(&mut *tx)
+            .await?;
+        for conflict in conflicts {
+            sqlx::query("INSERT OR IGNORE INTO task_conflicts (task_id, path, kind) VALUES (?, ?, ?)")
+                .bind(task_id)
+                .bind(&conflict.path)
+                .bind(&conflict.kind)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    // 广播任务变化，没有订阅者时忽略
+    fn publish(&self, update: TaskUpdate) {
+        let _ = self.update_tx.send(update);
+    }
+
+    // 通知日志订阅者任务已结束
+    fn notify_finished(&self, task_id: i64, status: TaskStatus) {
+        let _ = self.log_tx.send(LogEvent::Finished { task_id, status });
+    }
+
+    async fn current_status(&self, task_id: i64) -> Result<TaskStatus, Rejection> {
+        sqlx::query_as::<_, (TaskStatus,)>("SELECT status FROM tasks WHERE id = ?")
+            .bind(task_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .map(|(status,)| status)
+            .ok_or(Rejection::NotFound(task_id))
+    }
+
+    // 按 TaskStatus 的状态转换表检查能否转换到 next
+    async fn check_transition(&self, task_id: i64, next: TaskStatus) -> Result<(), Rejection> {
+        let current = self.current_status(task_id).await?;
+        if current.can_transition_to(next) {
+            Ok(())
+        } else {
+            Err(Rejection::Conflict(format!("Task {} cannot change from {} to {}", task_id, current, next)))
+        }
+    }
+
+    async fn set_status(&self, task_id: i64, status: TaskStatus) -> Result<Task, Rejection> {
+        self.check_transition(task_id, status).await?;
+        // 离开等待队列的任务不再有队列位置
+        let task = sqlx::query_as::<_, Task>(
+            "UPDATE tasks SET status = ?1, queue_position = CASE WHEN ?1 = ?2 THEN queue_position ELSE NULL END \
+             WHERE id = ?3 RETURNING *",
+        )
+        .bind(status)
+        .bind(TaskStatus::Queued)
+        .bind(task_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+        self.publish(TaskUpdate::StatusChanged(task.clone()));
+        Ok(task)
+    }
+
+    // 停止任务：运行中的任务会被中止并还原工作副本，排队中的任务直接取消
+    async fn stop_task(&mut self, task_id: i64) -> Result<(), Rejection> {
+        if self.is_running(task_id) {
+            self.check_transition(task_id, TaskStatus::Stopped).await?;
+            let running = self.running.remove(&task_id).unwrap();
+            // 中止后台执行，子进程随之被杀死
+            running.handle.abort();
+            let _ = running.handle.await;
+            let log = TaskLogger::new(self.db_pool.clone(), self.log_tx.clone(), task_id).await;
+            match self.repository_of(&running.task) {
+                Some(repository) => {
+                    log.system("Task stopped by user, reverting working copy").await;
+                    self.revert_workcopy(&repository, &running.task, &log).await;
+                }
+                None => log.system("Task stopped by user").await,
+            }
+            self.set_status(task_id, TaskStatus::Stopped).await?;
+            self.notify_finished(task_id, TaskStatus::Stopped);
+        } else {
+            self.set_status(task_id, TaskStatus::Cancelled).await?;
+            self.remove_waiting(task_id);
+            self.notify_finished(task_id, TaskStatus::Cancelled);
+        }
+        Ok(())
+    }
+
+    // 删除任务：运行中的任务需要先停止
+    async fn delete_task(&mut self, task_id: i64) -> Result<(), Rejection> {
+        let status = self.current_status(task_id).await?;
+        if !status.can_delete() || self.is_running(task_id) {
+            return Err(Rejection::Conflict(format!("Task {} is {}, stop it first", task_id, status)));
+        }
+        self.remove_waiting(task_id);
+        // 已成功的前置任务被删除时依赖已满足，从后续任务中去掉它，避免被当作已删除而取消
+        if status == TaskStatus::Succeeded {
+            self.detach_dependents(task_id).await?;
+        }
+
+        sqlx::query("DELETE FROM task_logs WHERE task_id = ?")
+            .bind(task_id)
+            .execute(&self.db_pool)
+            .await?;
+        sqlx::query("DELETE FROM task_conflicts WHERE task_id = ?")
+            .bind(task_id)
+            .execute(&self.db_pool)
+            .await?;
+        sqlx::query("DELETE FROM tasks WHERE id = ?")
+            .bind(task_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        self.publish(TaskUpdate::Deleted(task_id));
+        if !status.is_terminal() {
+            self.notify_finished(task_id, TaskStatus::Cancelled);
+        }
+        Ok(())
     }
 
-    async fn delete_task(&self, task_id: i64) {
-        // 实现删除任务的逻辑
+    // 从未结束的后续任务的前置任务列表中去掉指定任务
+    async fn detach_dependents(&mut self, task_id: i64) -> Result<(), Rejection> {
+        let dependents = sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE depends_on != ''")
+            .fetch_all(&self.db_pool)
+            .await?;
+        for dependent in dependents {
+            if dependent.status.is_terminal() || !dependent.dependencies().contains(&task_id) {
+                continue;
+            }
+            let depends_on = dependent
+                .dependencies()
+                .into_iter()
+                .filter(|&id| id != task_id)
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            sqlx::query("UPDATE tasks SET depends_on = ? WHERE id = ?")
+                .bind(&depends_on)
+                .bind(dependent.id)
+                .execute(&self.db_pool)
+                .await?;
+            if let Some(blocked) = self.blocked.iter_mut().find(|blocked| blocked.id == dependent.id) {
+                blocked.depends_on = depends_on;
+            }
+        }
+        Ok(())
+    }
+
+    // 从等待队列、阻塞列表和计时中移除任务
+    fn remove_waiting(&mut self, task_id: i64) {
+        self.queue.retain(|task| task.id != task_id);
+        self.blocked.retain(|task| task.id != task_id);
+        if let Some(handle) = self.timers.remove(&task_id) {
+            handle.abort();
+        }
+    }
+
+    // 清理被中断的合并留下的锁和修改
+    async fn revert_workcopy(&self, repository: &Repository, task: &Task, log: &TaskLogger) {
+        let workcopy = repository.vcs.workcopy_path(&task.target_branch);
+        if !workcopy.exists() {
+            return;
+        }
+        let _guard = repository.workcopies.lock(&task.target_branch, &format!("task {}", task.id)).await;
+        if let Err(e) = repository.vcs.revert(&workcopy, log).await {
+            log.system(&format!("Failed to revert working copy: {:#}", e)).await;
+        }
     }
 }
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64)
+}
@@ -0,0 +1,467 @@
+// src/vcs/git.rs
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use models::vcs::{Branch, ChangedPath, Commit, Conflict, MergePreview};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+use super::{run_command, CommandOutput, InvalidRevisions, VersionControl};
+use crate::config::RepositoryConfig;
+use crate::logs::TaskLogger;
+
+// 合并提交中记录来源提交的 trailer，与 cherry-pick -x 的格式相同
+const PICKED_TRAILER: &str = "(cherry picked from commit ";
+
+// 基于 git 命令行的版本库访问，合并通过 cherry-pick 实现
+pub struct Git {
+    config: RepositoryConfig,
+    mirror_lock: Mutex<()>, // 串行化镜像的克隆与更新
+}
+
+impl Git {
+    pub fn new(config: RepositoryConfig) -> Self {
+        Git {
+            config,
+            mirror_lock: Mutex::new(()),
+        }
+    }
+
+    // 执行 git 子命令，禁止交互式输入凭据
+    // 传入 log 时，输出会逐行写入任务日志
+    pub async fn run(&self, args: &[&str], cwd: Option<&Path>, log: Option<&TaskLogger>) -> anyhow::Result<CommandOutput> {
+        let mut command = Command::new("git");
+        command.args(args).env("GIT_TERMINAL_PROMPT", "0");
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        run_command(command, &format!("git {}", args.join(" ")), log).await
+    }
+
+    // 更新用于查询分支和提交的裸镜像，不存在时先克隆
+    async fn sync_mirror(&self) -> anyhow::Result<PathBuf> {
+        let _guard = self.mirror_lock.lock().await;
+        let mirror = self.config.workcopy_dir.join(&self.config.name).join(".mirror.git");
+        if mirror.exists() {
+            self.run(&["remote", "update", "--prune"], Some(&mirror), None).await?.check("git remote update")?;
+        } else {
+            if let Some(parent) = mirror.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let mirror_str = mirror.to_string_lossy();
+            self.run(&["clone", "--mirror", &self.config.url, &mirror_str], None, None).await?.check("git clone")?;
+        }
+        Ok(mirror)
+    }
+
+    // 目标分支上合并提交的 trailer 中记录的来源提交
+    async fn picked_commits(&self, mirror: &Path, target_branch: &str, source_branch: &str) -> anyhow::Result<HashSet<String>> {
+        let range = format!("refs/heads/{}..refs/heads/{}", source_branch, target_branch);
+        let output = self
+            .run(&["log", "--fixed-strings", "--grep", PICKED_TRAILER, "--format=%B", &range, "--"], Some(mirror), None)
+            .await?
+            .check("git log")?;
+        Ok(parse_picked(&output.stdout))
+    }
+
+    // 准备分支的工作副本：存在时还原并更新到远程分支，不存在时克隆
+    // 已有的工作副本不是该版本库的克隆时删除后重新克隆
+    async fn prepare(&self, branch: &str, log: Option<&TaskLogger>) -> anyhow::Result<PathBuf> {
+        let workcopy = self.workcopy_path(branch);
+        if workcopy.join(".git").exists() {
+            let output = self.run(&["remote", "get-url", "origin"], Some(&workcopy), log).await?;
+            if !output.success() || output.stdout.trim() != self.config.url {
+                if let Some(log) = log {
+                    log.system(&format!("{} is not a clone of {}, cloning again", workcopy.display(), self.config.url)).await;
+                }
+                tokio::fs::remove_dir_all(&workcopy).await?;
+            }
+        }
+        if workcopy.join(".git").exists() {
+            self.revert_workcopy(&workcopy, log).await?;
+            self.run(&["fetch", "--prune", "origin"], Some(&workcopy), log).await?.check("git fetch")?;
+            let remote_branch = format!("origin/{}", branch);
+            self.run(&["checkout", "-B", branch, &remote_branch], Some(&workcopy), log)
+                .await?
+                .check("git checkout")?;
+        } else {
+            if let Some(parent) = workcopy.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let workcopy_str = workcopy.to_string_lossy();
+            self.run(&["clone", "--branch", branch, &self.config.url, &workcopy_str], None, log)
+                .await?
+                .check("git clone")?;
+        }
+        Ok(workcopy)
+    }
+
+    async fn revert_workcopy(&self, workcopy: &Path, log: Option<&TaskLogger>) -> anyhow::Result<()> {
+        self.run(&["reset", "--hard"], Some(workcopy), log).await?.check("git reset")?;
+        // 没有进行中的 cherry-pick 时该命令会失败，忽略即可
+        self.run(&["cherry-pick", "--quit"], Some(workcopy), log).await?;
+        self.run(&["clean", "-fd"], Some(workcopy), log).await?.check("git clean")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VersionControl for Git {
+    async fn list_branches(&self) -> anyhow::Result<Vec<Branch>> {
+        let mirror = self.sync_mirror().await?;
+        let output = self
+            .run(
+                &[
+                    "for-each-ref",
+                    "--format=%(refname:short)%09%(objectname)%09%(authorname)%09%(committerdate:iso-strict)",
+                    "refs/heads",
+                ],
+                Some(&mirror),
+                None,
+            )
+            .await?
+            .check("git for-each-ref")?;
+
+        let branches = output
+            .stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(Branch {
+                    name: fields.next()?.to_string(),
+                    revision: fields.next()?.to_string(),
+                    author: fields.next()?.to_string(),
+                    date: fields.next()?.to_string(),
+                })
+            })
+            .collect();
+        Ok(branches)
+    }
+
+    async fn list_commits(&self, branch: &str, limit: u32, before_revision: Option<&str>) -> anyhow::Result<Vec<Commit>> {
+        let mirror = self.sync_mirror().await?;
+        // 翻页时从 before_revision 开始多取一条，再跳过它本身
+        // before_revision 来自客户端，只接受完整的提交哈希，避免被当作 git 选项
+        let (start, count, skip) = match before_revision {
+            Some(revision) if !is_commit_hash(revision) => {
+                return Err(InvalidRevisions(format!("{} is not a commit hash", revision)).into());
+            }
+            Some(revision) => (revision.to_string(), limit + 1, 1),
+            None => (format!("refs/heads/{}", branch), limit, 0),
+        };
+        let count = count.to_string();
+        let output = self
+            .run(
+                &["log", "-n", &count, "--format=%x1e%H%x1f%an%x1f%aI%x1f%B%x1f", "--name-status", "--end-of-options", &start, "--"],
+                Some(&mirror),
+                None,
+            )
+            .await?
+            .check("git log")?;
+        Ok(parse_log(&output.stdout).into_iter().skip(skip).collect())
+    }
+
+    // 每个提交都必须能解析且可从来源分支到达，结果为完整哈希，按提交时间从旧到新排列
+    async fn normalize_revisions(&self, source_branch: &str, revisions: &str) -> anyhow::Result<String> {
+        let mirror = self.sync_mirror().await?;
+        let source_ref = format!("refs/heads/{}", source_branch);
+        let mut hashes: Vec<String> = Vec::new();
+        for revision in revisions.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+            let spec = format!("{}^{{commit}}", revision);
+            let output = self.run(&["rev-parse", "--verify", "--quiet", &spec], Some(&mirror), None).await?;
+            if !output.success() {
+                return Err(InvalidRevisions(format!("{} is not a commit", revision)).into());
+            }
+            let hash = output.stdout.trim().to_string();
+            let output = self
+                .run(&["merge-base", "--is-ancestor", &hash, &source_ref], Some(&mirror), None)
+                .await?;
+            if !output.success() {
+                return Err(InvalidRevisions(format!("{} is not a commit on {}", revision, source_branch)).into());
+            }
+            if !hashes.contains(&hash) {
+                hashes.push(hash);
+            }
+        }
+        if hashes.is_empty() {
+            return Err(InvalidRevisions("no revisions given".to_string()).into());
+        }
+
+        // cherry-pick 需要按提交先后应用
+        let mut args = vec!["rev-list", "--no-walk=sorted"];
+        args.extend(hashes.iter().map(String::as_str));
+        let output = self.run(&args, Some(&mirror), None).await?.check("git rev-list")?;
+        let sorted: Vec<&str> = output.stdout.lines().rev().collect();
+        Ok(sorted.join(","))
+    }
+
+    async fn commits(&self, _source_branch: &str, revisions: &[String]) -> anyhow::Result<Vec<Commit>> {
+        let mirror = self.sync_mirror().await?;
+        let mut args = vec!["log", "--no-walk=sorted", "--format=%x1e%H%x1f%an%x1f%aI%x1f%B%x1f"];
+        args.extend(revisions.iter().map(String::as_str));
+        args.push("--");
+        let output = self.run(&args, Some(&mirror), None).await?.check("git log")?;
+        Ok(parse_log(&output.stdout).into_iter().rev().collect())
+    }
+
+    // 多个提交合并为一个提交，补丁与任何原提交都不同，因此除 --cherry-pick 外
+    // 还要排除合并提交的 trailer 中记录的提交
+    async fn eligible_commits(&self, target_branch: &str, source_branch: &str) -> anyhow::Result<Vec<Commit>> {
+        let mirror = self.sync_mirror().await?;
+        let picked = self.picked_commits(&mirror, target_branch, source_branch).await?;
+        let range = format!("refs/heads/{}...refs/heads/{}", target_branch, source_branch);
+        let output = self
+            .run(
+                &["log", "--cherry-pick", "--right-only", "--no-merges", "--format=%x1e%H%x1f%an%x1f%aI%x1f%B%x1f", "--name-status", &range, "--"],
+                Some(&mirror),
+                None,
+            )
+            .await?
+            .check("git log")?;
+        Ok(parse_log(&output.stdout)
+            .into_iter()
+            .filter(|commit| !picked.contains(&commit.revision))
+            .collect())
+    }
+
+    async fn ensure_eligible(&self, target_branch: &str, source_branch: &str, revisions: &str) -> anyhow::Result<()> {
+        let mirror = self.sync_mirror().await?;
+        let picked = self.picked_commits(&mirror, target_branch, source_branch).await?;
+        let range = format!("refs/heads/{}...refs/heads/{}", target_branch, source_branch);
+        let output = self
+            .run(&["rev-list", "--cherry-pick", "--right-only", "--no-merges", &range, "--"], Some(&mirror), None)
+            .await?
+            .check("git rev-list")?;
+        let eligible: HashSet<&str> = output.stdout.lines().filter(|hash| !picked.contains(*hash)).collect();
+        let merged: Vec<&str> = revisions.split(',').filter(|revision| !eligible.contains(revision)).collect();
+        if merged.is_empty() {
+            return Ok(());
+        }
+        Err(InvalidRevisions(format!("{} already merged into {}", merged.join(","), target_branch)).into())
+    }
+
+    fn workcopy_path(&self, branch: &str) -> PathBuf {
+        self.config.workcopy_path(branch)
+    }
+
+    async fn prepare_workcopy(&self, branch: &str, log: &TaskLogger) -> anyhow::Result<PathBuf> {
+        self.prepare(branch, Some(log)).await
+    }
+
+    // cherry-pick 按提交哈希应用修改，来源分支只用于说明
+    // 所有提交合并为一个提交，来源提交在提交时写入 trailer
+    async fn merge_revisions(&self, workcopy: &Path, _source_branch: &str, revisions: &[String], log: &TaskLogger) -> anyhow::Result<()> {
+        let mut args = vec!["cherry-pick", "--no-commit"];
+        args.extend(revisions.iter().map(String::as_str));
+        self.run(&args, Some(workcopy), Some(log)).await?.check("git cherry-pick")?;
+        Ok(())
+    }
+
+    // git 没有试合并，在工作副本上 cherry-pick 后读取状态，再还原
+    async fn preview_merge(&self, branch: &str, _source_branch: &str, revisions: &[String]) -> anyhow::Result<MergePreview> {
+        let workcopy = self.prepare(branch, None).await?;
+        let mut args = vec!["cherry-pick", "--no-commit"];
+        args.extend(revisions.iter().map(String::as_str));
+        // 有冲突时 cherry-pick 以非零退出码结束，冲突从状态中读取
+        self.run(&args, Some(&workcopy), None).await?;
+        let status = self.run(&["status", "--porcelain"], Some(&workcopy), None).await;
+        self.revert_workcopy(&workcopy, None).await?;
+        let status = status?.check("git status")?;
+        Ok(MergePreview {
+            revisions: revisions.join(","),
+            paths: parse_changed_paths(&status.stdout),
+            conflicts: parse_conflicts(&status.stdout),
+            ..Default::default()
+        })
+    }
+
+    async fn commit(&self, workcopy: &Path, message: &str, revisions: &[String], log: &TaskLogger) -> anyhow::Result<()> {
+        let message = with_picked_trailers(message, revisions);
+        self.run(&["commit", "-m", &message], Some(workcopy), Some(log)).await?.check("git commit")?;
+        self.run(&["push", "origin", "HEAD"], Some(workcopy), Some(log)).await?.check("git push")?;
+        Ok(())
+    }
+
+    async fn revert(&self, workcopy: &Path, log: &TaskLogger) -> anyhow::Result<()> {
+        self.revert_workcopy(workcopy, Some(log)).await
+    }
+
+    async fn conflicts(&self, workcopy: &Path) -> anyhow::Result<Vec<Conflict>> {
+        let output = self.run(&["status", "--porcelain"], Some(workcopy), None).await?.check("git status")?;
+        Ok(parse_conflicts(&output.stdout))
+    }
+
+    async fn has_changes(&self, workcopy: &Path) -> anyhow::Result<bool> {
+        let output = self.run(&["status", "--porcelain"], Some(workcopy), None).await?.check("git status")?;
+        Ok(!output.stdout.trim().is_empty())
+    }
+}
+
+// 解析 git log 输出：每条提交以 0x1e 开头，字段以 0x1f 分隔，
+// 最后一个字段为 --name-status 输出的改动列表
+fn parse_log(output: &str) -> Vec<Commit> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.split('\x1f');
+            let revision = fields.next()?.trim().to_string();
+            if revision.is_empty() {
+                return None;
+            }
+            Some(Commit {
+                revision,
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                message: fields.next()?.trim().to_string(),
+                changed_paths: fields
+                    .next()
+                    .unwrap_or_default()
+                    .lines()
+                    .filter_map(|line| {
+                        let (action, path) = line.split_once('\t')?;
+                        Some(ChangedPath {
+                            action: action.chars().take(1).collect(),
+                            path: path.to_string(),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+// 40 位十六进制的完整提交哈希
+fn is_commit_hash(revision: &str) -> bool {
+    revision.len() == 40 && revision.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+// 在提交信息末尾为每个来源提交加上 trailer
+fn with_picked_trailers(message: &str, revisions: &[String]) -> String {
+    let trailers: Vec<String> = revisions.iter().map(|hash| format!("{}{})", PICKED_TRAILER, hash)).collect();
+    format!("{}\n\n{}", message.trim_end(), trailers.join("\n"))
+}
+
+// 从提交信息中取出 trailer 记录的来源提交
+fn parse_picked(messages: &str) -> HashSet<String> {
+    messages
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(PICKED_TRAILER)?.strip_suffix(')'))
+        .map(str::to_string)
+        .collect()
+}
+
+// 解析 git status --porcelain 输出中改动的路径，未合并的路径记为 C
+fn parse_changed_paths(output: &str) -> Vec<ChangedPath> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (code, path) = (line.get(..2)?, line.get(3..)?);
+            let action = if code.contains('U') || code == "AA" || code == "DD" {
+                "C".to_string()
+            } else {
+                code.chars().find(|c| *c != ' ' && *c != '?')?.to_string()
+            };
+            Some(ChangedPath {
+                action,
+                path: path.to_string(),
+            })
+        })
+        .collect()
+}
+
+// 解析 git status --porcelain 输出中的未合并路径：
+// UU / AA 为内容冲突，其余组合为一方删除等树冲突
+fn parse_conflicts(output: &str) -> Vec<Conflict> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (code, path) = (line.get(..2)?, line.get(3..)?);
+            let kind = match code {
+                "UU" | "AA" => "text",
+                "DD" | "AU" | "UD" | "UA" | "DU" => "tree",
+                _ => return None,
+            };
+            Some(Conflict {
+                path: path.to_string(),
+                kind: kind.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::vcs::VcsKind;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    fn commit_file(dir: &Path, name: &str) -> String {
+        std::fs::write(dir.join(name), name).unwrap();
+        git(dir, &["add", name]);
+        git(dir, &["commit", "-m", name]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn only_full_hashes_are_commit_hashes() {
+        assert!(is_commit_hash("0123456789abcdef0123456789ABCDEF01234567"));
+        assert!(!is_commit_hash("--output=/tmp/x"));
+        assert!(!is_commit_hash("HEAD~1"));
+        assert!(!is_commit_hash("0123456"));
+    }
+
+    // 多个提交合并为一个提交后，再次提交这些提交会被拒绝
+    #[tokio::test]
+    async fn merged_commits_are_not_eligible() {
+        let root = std::env::temp_dir().join(format!("task_web_git_{}", std::process::id()));
+        let origin = root.join("origin");
+        std::fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "-b", "main"]);
+        commit_file(&origin, "base");
+        git(&origin, &["checkout", "-b", "dev"]);
+        let first = commit_file(&origin, "first");
+        let second = commit_file(&origin, "second");
+        let third = commit_file(&origin, "third");
+        git(&origin, &["checkout", "main"]);
+        git(&origin, &["cherry-pick", "--no-commit", &first, &second]);
+        let picked = [first.clone(), second.clone()];
+        git(&origin, &["commit", "-m", &with_picked_trailers("Merge dev", &picked)]);
+
+        let vcs = Git::new(RepositoryConfig {
+            name: "test".to_string(),
+            kind: VcsKind::Git,
+            url: origin.to_string_lossy().to_string(),
+            merge_source: "dev".to_string(),
+            workcopy_dir: root.join("workcopies"),
+            ..Default::default()
+        });
+        let eligible: Vec<String> = vcs
+            .eligible_commits("main", "dev")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.revision)
+            .collect();
+        let resubmitted = vcs.ensure_eligible("main", "dev", &format!("{},{}", second, third)).await;
+        let remaining = vcs.ensure_eligible("main", "dev", &third).await;
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(eligible, vec![third]);
+        let error = resubmitted.unwrap_err();
+        assert!(error.downcast_ref::<InvalidRevisions>().is_some());
+        assert!(error.to_string().contains(&second));
+        assert!(remaining.is_ok());
+    }
+}
@@ -0,0 +1,311 @@
+// src/vcs/mod.rs
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use models::vcs::{Branch, Commit, Conflict, MergePreview, Repository as RepositoryInfo, VcsKind};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::cache::TtlCache;
+use crate::config::RepositoryConfig;
+use crate::logs::TaskLogger;
+
+use pool::WorkcopyPool;
+
+pub mod git;
+pub mod pool;
+pub mod svn;
+
+// 版本控制系统的统一接口，合并任务通过它操作工作副本
+// 返回 CommandFailed 错误的操作表示命令本身执行失败，其退出码会记录到任务上
+#[async_trait]
+pub trait VersionControl: Send + Sync {
+    async fn list_branches(&self) -> anyhow::Result<Vec<Branch>>;
+
+    // 从新到旧列出分支上的提交，before_revision 用于翻页
+    async fn list_commits(&self, branch: &str, limit: u32, before_revision: Option<&str>) -> anyhow::Result<Vec<Commit>>;
+
+    // 校验修订列表中的修订都位于来源分支上，返回规范化后的修订列表
+    // 写法或修订本身有误时返回 InvalidRevisions 错误
+    async fn normalize_revisions(&self, source_branch: &str, revisions: &str) -> anyhow::Result<String>;
+
+    // 按从旧到新的顺序列出规范化修订列表中的提交，用于生成提交信息
+    async fn commits(&self, source_branch: &str, revisions: &[String]) -> anyhow::Result<Vec<Commit>>;
+
+    // 列出来源分支上尚未合并到目标分支的提交，从新到旧排列
+    async fn eligible_commits(&self, target_branch: &str, source_branch: &str) -> anyhow::Result<Vec<Commit>>;
+
+    // 检查规范化后的修订列表中没有已合并到目标分支的修订，否则返回 InvalidRevisions 错误
+    async fn ensure_eligible(&self, target_branch: &str, source_branch: &str, revisions: &str) -> anyhow::Result<()>;
+
+    // 分支对应的本地工作副本路径
+    fn workcopy_path(&self, branch: &str) -> PathBuf;
+
+    // 准备分支的工作副本：不存在时检出，存在时还原并更新到最新
+    async fn prepare_workcopy(&self, branch: &str, log: &TaskLogger) -> anyhow::Result<PathBuf>;
+
+    // 将来源分支上的指定修订合并到工作副本，不提交
+    async fn merge_revisions(&self, workcopy: &Path, source_branch: &str, revisions: &[String], log: &TaskLogger) -> anyhow::Result<()>;
+
+    // 在分支的工作副本上试合并，返回会改动的路径和预计的冲突，结束后工作副本保持不变
+    // revisions 应为 normalize_revisions 返回的规范化列表
+    async fn preview_merge(&self, branch: &str, source_branch: &str, revisions: &[String]) -> anyhow::Result<MergePreview>;
+
+    // 提交工作副本中的修改并推送到版本库，revisions 为本次合并的修订
+    async fn commit(&self, workcopy: &Path, message: &str, revisions: &[String], log: &TaskLogger) -> anyhow::Result<()>;
+
+    // 丢弃工作副本中的所有修改，包括未完成的合并
+    async fn revert(&self, workcopy: &Path, log: &TaskLogger) -> anyhow::Result<()>;
+
+    // 列出工作副本中处于冲突状态的路径
+    async fn conflicts(&self, workcopy: &Path) -> anyhow::Result<Vec<Conflict>>;
+
+    // 工作副本是否存在未提交的修改
+    async fn has_changes(&self, workcopy: &Path) -> anyhow::Result<bool>;
+}
+
+// 一个已配置的版本库
+pub struct Repository {
+    pub name: String,
+    pub kind: VcsKind,
+    pub merge_source: String, // 默认的合并来源分支
+    pub commit_template: String,
+    pub vcs: Arc<dyn VersionControl>,
+    pub branch_cache: TtlCache<Vec<Branch>>,
+    pub workcopies: WorkcopyPool,
+}
+
+impl Repository {
+    pub fn new(config: RepositoryConfig) -> Self {
+        let vcs: Arc<dyn VersionControl> = match config.kind {
+            VcsKind::Svn => Arc::new(svn::Svn::new(config.clone())),
+            VcsKind::Git => Arc::new(git::Git::new(config.clone())),
+        };
+        let workcopies = WorkcopyPool::new(config.workcopy_dir.join(&config.name));
+        Repository {
+            name: config.name,
+            kind: config.kind,
+            merge_source: config.merge_source,
+            commit_template: config.commit_template,
+            vcs,
+            branch_cache: TtlCache::new(Duration::from_secs(config.branch_cache_ttl_secs)),
+            workcopies,
+        }
+    }
+
+    // 任务的来源分支，为空时使用默认来源分支
+    pub fn source_branch<'a>(&'a self, source_branch: &'a str) -> &'a str {
+        if source_branch.is_empty() {
+            &self.merge_source
+        } else {
+            source_branch
+        }
+    }
+
+    pub fn info(&self) -> RepositoryInfo {
+        RepositoryInfo {
+            name: self.name.clone(),
+            kind: self.kind,
+            merge_source: self.merge_source.clone(),
+        }
+    }
+}
+
+// 所有已配置的版本库，第一个为默认版本库
+pub struct Repositories {
+    repositories: Vec<Arc<Repository>>,
+}
+
+impl Repositories {
+    pub fn new(configs: Vec<RepositoryConfig>) -> Self {
+        Repositories {
+            repositories: configs.into_iter().map(|config| Arc::new(Repository::new(config))).collect(),
+        }
+    }
+
+    // 按名称查找版本库，名称为空时返回默认版本库
+    pub fn get(&self, name: &str) -> Option<Arc<Repository>> {
+        if name.is_empty() {
+            return self.repositories.first().cloned();
+        }
+        self.repositories.iter().find(|repository| repository.name == name).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Repository>> {
+        self.repositories.iter()
+    }
+}
+
+// 一次命令执行的结果
+pub struct CommandOutput {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == 0
+    }
+
+    // 命令失败时转换为 CommandFailed 错误
+    pub fn check(self, command: &str) -> anyhow::Result<Self> {
+        if self.success() {
+            Ok(self)
+        } else {
+            Err(CommandFailed {
+                command: command.to_string(),
+                code: self.code,
+                stderr: self.stderr.trim().to_string(),
+            }
+            .into())
+        }
+    }
+}
+
+// 版本控制命令以非零退出码结束
+#[derive(Debug)]
+pub struct CommandFailed {
+    pub command: String,
+    pub code: i32,
+    pub stderr: String,
+}
+
+impl fmt::Display for CommandFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed with exit code {}: {}", self.command, self.code, self.stderr)
+    }
+}
+
+impl std::error::Error for CommandFailed {}
+
+// 任务的修订列表无法解析，或其中的修订不在来源分支上
+#[derive(Debug)]
+pub struct InvalidRevisions(pub String);
+
+impl fmt::Display for InvalidRevisions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidRevisions {}
+
+// 检查分支名能安全地用作工作副本目录和版本库路径：
+// 不能为空，不能以 / 开头或结尾，各段不能为空、. 或 ..，不能含控制字符
+pub fn check_branch_name(branch: &str) -> Result<(), String> {
+    if branch.is_empty() {
+        return Err("Branch name is required".to_string());
+    }
+    if branch.chars().any(char::is_control) || branch.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return Err(format!("Invalid branch name {:?}", branch));
+    }
+    Ok(())
+}
+
+// 还原 %XX 编码的内容，用于工作副本目录名和 svn 输出的地址
+pub fn percent_decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+// 执行命令并收集输出，display 为写入日志的命令行（不含密码等敏感参数）
+// 传入 log 时，输出会逐行写入任务日志
+pub async fn run_command(mut command: Command, display: &str, log: Option<&TaskLogger>) -> anyhow::Result<CommandOutput> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // 子进程自成一个进程组，任务被停止或超时时连同其派生的进程（如 ssh）一起结束
+    #[cfg(unix)]
+    command.process_group(0);
+
+    if let Some(log) = log {
+        log.system(&format!("$ {}", display)).await;
+    }
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("failed to spawn {}", display))?;
+    let group = ProcessGroup(child.id());
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    let (stdout, stderr) = tokio::join!(
+        read_lines(stdout, "stdout", log),
+        read_lines(stderr, "stderr", log),
+    );
+    let status = child.wait().await?;
+    group.finished();
+
+    Ok(CommandOutput {
+        code: status.code().unwrap_or(-1),
+        stdout: stdout?,
+        stderr: stderr?,
+    })
+}
+
+// 命令未执行完毕就被丢弃（所在的任务被停止或超时）时结束整个进程组
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    fn finished(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            // 进程组 ID 即子进程的 PID，只向该命令创建的进程组发送信号
+            unsafe {
+                libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+// 逐行读取子进程输出，非 UTF-8 内容按有损方式转换
+async fn read_lines(reader: impl AsyncRead + Unpin, stream: &str, log: Option<&TaskLogger>) -> anyhow::Result<String> {
+    let mut reader = BufReader::new(reader);
+    let mut output = String::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).await? == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buf);
+        if let Some(log) = log {
+            log.append(stream, line.trim_end_matches(['\r', '\n'])).await;
+        }
+        output.push_str(&line);
+    }
+    Ok(output)
+}
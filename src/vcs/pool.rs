@@ -0,0 +1,209 @@
+// src/vcs/pool.rs
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use models::vcs::WorkcopyStatus;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use super::percent_decode;
+
+// 一个版本库下按分支保留的工作副本，目录名为编码后的分支名（见 dir_name）
+// 同一工作副本同一时间只允许一个任务或预览使用，长时间未使用的会被回收
+pub struct WorkcopyPool {
+    root: PathBuf,
+    entries: Mutex<HashMap<String, Arc<Entry>>>,
+}
+
+struct Entry {
+    lock: Arc<AsyncMutex<()>>,
+    usage: Mutex<Usage>,
+}
+
+#[derive(Default)]
+struct Usage {
+    branch: Option<String>,
+    holder: Option<String>,         // 当前使用者，如 "task 12"、"preview"
+    last_used: Option<SystemTime>,  // 本次运行中最后一次释放的时间
+}
+
+// 占用工作副本的守卫，丢弃时释放并记录使用时间
+pub struct WorkcopyGuard {
+    entry: Arc<Entry>,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for WorkcopyGuard {
+    fn drop(&mut self) {
+        let mut usage = self.entry.usage.lock().unwrap();
+        usage.holder = None;
+        usage.last_used = Some(SystemTime::now());
+    }
+}
+
+impl WorkcopyPool {
+    pub fn new(root: PathBuf) -> Self {
+        WorkcopyPool {
+            root,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn entry(&self, name: &str) -> Arc<Entry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                Arc::new(Entry {
+                    lock: Arc::new(AsyncMutex::new(())),
+                    usage: Mutex::new(Usage::default()),
+                })
+            })
+            .clone()
+    }
+
+    fn guard(entry: Arc<Entry>, guard: OwnedMutexGuard<()>, branch: &str, holder: &str) -> WorkcopyGuard {
+        {
+            let mut usage = entry.usage.lock().unwrap();
+            usage.branch = Some(branch.to_string());
+            usage.holder = Some(holder.to_string());
+        }
+        WorkcopyGuard { entry, _guard: guard }
+    }
+
+    // 独占分支的工作副本，被占用时等待
+    pub async fn lock(&self, branch: &str, holder: &str) -> WorkcopyGuard {
+        let entry = self.entry(&dir_name(branch));
+        let guard = entry.lock.clone().lock_owned().await;
+        Self::guard(entry, guard, branch, holder)
+    }
+
+    // 工作副本被占用时立即返回 None
+    pub fn try_lock(&self, branch: &str, holder: &str) -> Option<WorkcopyGuard> {
+        let entry = self.entry(&dir_name(branch));
+        let guard = entry.lock.clone().try_lock_owned().ok()?;
+        Some(Self::guard(entry, guard, branch, holder))
+    }
+
+    // 列出磁盘上的工作副本和正在使用的工作副本
+    pub async fn status(&self, repository: &str) -> Vec<WorkcopyStatus> {
+        let mut names = self.workcopy_dirs().await;
+        for name in self.entries.lock().unwrap().keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names.sort();
+
+        let mut statuses = Vec::with_capacity(names.len());
+        for name in names {
+            let path = self.root.join(&name);
+            let entry = self.entry(&name);
+            let (branch, holder, last_used) = {
+                let usage = entry.usage.lock().unwrap();
+                (usage.branch.clone(), usage.holder.clone(), usage.last_used)
+            };
+            // 已被回收且未在使用的条目不再列出
+            if holder.is_none() && !path.exists() {
+                continue;
+            }
+            let last_used = match last_used {
+                Some(time) => Some(time),
+                None => modified_time(&path).await,
+            };
+            statuses.push(WorkcopyStatus {
+                repository: repository.to_string(),
+                branch: branch.unwrap_or_else(|| percent_decode(&name)),
+                path: path.to_string_lossy().to_string(),
+                exists: path.exists(),
+                in_use: holder.is_some(),
+                holder,
+                idle_secs: last_used.and_then(|time| time.elapsed().ok()).map(|idle| idle.as_secs()),
+            });
+        }
+        statuses
+    }
+
+    // 删除空闲超过 max_idle 的工作副本，返回被删除的目录名
+    // 本次运行中未使用过的工作副本以目录的修改时间作为最后使用时间
+    pub async fn collect_garbage(&self, max_idle: Duration) -> Vec<String> {
+        let mut removed = Vec::new();
+        for name in self.workcopy_dirs().await {
+            let entry = self.entry(&name);
+            let Ok(_guard) = entry.lock.clone().try_lock_owned() else {
+                continue;
+            };
+            let path = self.root.join(&name);
+            let last_used = entry.usage.lock().unwrap().last_used;
+            let last_used = match last_used {
+                Some(time) => Some(time),
+                None => modified_time(&path).await,
+            };
+            let idle = last_used.and_then(|time| time.elapsed().ok()).unwrap_or_default();
+            if idle < max_idle {
+                continue;
+            }
+            // 条目本身保留，避免与正在等待该锁的使用者各持一把锁
+            match tokio::fs::remove_dir_all(&path).await {
+                Ok(()) => {
+                    *entry.usage.lock().unwrap() = Usage::default();
+                    removed.push(name);
+                }
+                Err(e) => eprintln!("Failed to remove working copy {}: {}", path.display(), e),
+            }
+        }
+        removed
+    }
+
+    // 根目录下的工作副本目录，以 . 开头的目录（如 Git 镜像）不属于工作副本
+    async fn workcopy_dirs(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let Ok(mut dirs) = tokio::fs::read_dir(&self.root).await else {
+            return names;
+        };
+        while let Ok(Some(dir)) = dirs.next_entry().await {
+            let name = dir.file_name().to_string_lossy().to_string();
+            if !name.starts_with('.') && dir.file_type().await.is_ok_and(|file_type| file_type.is_dir()) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+// 分支对应的工作副本目录名：字母、数字和 - _ . 以外的字节编码为 %XX，开头的 . 也编码，
+// 因此不同分支的目录名不会相同，也不会与 . 开头的目录（如 Git 镜像）混淆
+pub fn dir_name(branch: &str) -> String {
+    let mut name = String::with_capacity(branch.len());
+    for (index, byte) in branch.bytes().enumerate() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' || (byte == b'.' && index > 0) {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    name
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_names_are_distinct_and_stay_inside_the_pool() {
+        assert_eq!(dir_name("release-1.0"), "release-1.0");
+        assert_ne!(dir_name("a/b"), dir_name("a_b"));
+        assert_eq!(dir_name("a/b"), "a%2Fb");
+        assert_eq!(dir_name("a%2Fb"), "a%252Fb");
+        assert_eq!(dir_name("."), "%2E");
+        assert_eq!(dir_name(".."), "%2E.");
+        assert_eq!(percent_decode(&dir_name("feature/ä x")), "feature/ä x");
+    }
+}
@@ -0,0 +1,454 @@
+// src/vcs/svn.rs
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use models::revision::RevisionSet;
+use models::vcs::{Branch, ChangedPath, Commit, Conflict, MergePreview};
+use tokio::process::Command;
+
+use super::{percent_decode, run_command, CommandOutput, InvalidRevisions, VersionControl};
+use crate::config::RepositoryConfig;
+use crate::logs::TaskLogger;
+
+// 基于 svn 命令行的版本库访问
+pub struct Svn {
+    config: RepositoryConfig,
+}
+
+impl Svn {
+    pub fn new(config: RepositoryConfig) -> Self {
+        Svn { config }
+    }
+
+    fn root_url(&self) -> &str {
+        self.config.url.trim_end_matches('/')
+    }
+
+    // 分支对应的仓库地址，trunk 单独处理，其余位于 branches/ 下
+    pub fn branch_url(&self, branch: &str) -> String {
+        let root = self.root_url();
+        if branch == "trunk" {
+            format!("{}/trunk", root)
+        } else {
+            format!("{}/branches/{}", root, branch)
+        }
+    }
+
+    // 执行 svn 子命令，始终以非交互模式运行
+    // 传入 log 时，输出会逐行写入任务日志
+    pub async fn run(&self, args: &[&str], cwd: Option<&Path>, log: Option<&TaskLogger>) -> anyhow::Result<CommandOutput> {
+        let mut command = Command::new("svn");
+        command.args(args).arg("--non-interactive");
+        if let Some(username) = &self.config.username {
+            command.arg("--username").arg(username);
+        }
+        if let Some(password) = &self.config.password {
+            command.arg("--password").arg(password);
+        }
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        run_command(command, &format!("svn {}", args.join(" ")), log).await
+    }
+
+    // 来源分支中已合并（merged）或尚未合并（eligible）到目标分支的修订
+    async fn mergeinfo(&self, show_revs: &str, target_branch: &str, source_branch: &str) -> anyhow::Result<HashSet<i64>> {
+        let source_url = self.branch_url(source_branch);
+        let target_url = self.branch_url(target_branch);
+        let output = self
+            .run(&["mergeinfo", "--show-revs", show_revs, &source_url, &target_url], None, None)
+            .await?
+            .check("svn mergeinfo")?;
+        // 不可继承的合并记录为 r123*
+        let revisions = output
+            .stdout
+            .lines()
+            .filter_map(|line| line.trim().trim_start_matches('r').trim_end_matches('*').parse().ok())
+            .collect();
+        Ok(revisions)
+    }
+
+    // 准备分支的工作副本：存在时还原并更新，不存在时检出
+    // 已有的工作副本不是该分支的检出时删除后重新检出
+    async fn prepare(&self, branch: &str, log: Option<&TaskLogger>) -> anyhow::Result<PathBuf> {
+        let workcopy = self.workcopy_path(branch);
+        let branch_url = self.branch_url(branch);
+        if workcopy.join(".svn").exists() {
+            let output = self.run(&["info", "--show-item", "url"], Some(&workcopy), log).await?;
+            if !output.success() || percent_decode(output.stdout.trim()) != percent_decode(&branch_url) {
+                if let Some(log) = log {
+                    log.system(&format!("{} is not a checkout of {}, checking out again", workcopy.display(), branch_url)).await;
+                }
+                tokio::fs::remove_dir_all(&workcopy).await?;
+            }
+        }
+        if workcopy.join(".svn").exists() {
+            self.revert_workcopy(&workcopy, log).await?;
+            self.run(&["update"], Some(&workcopy), log).await?.check("svn update")?;
+        } else {
+            // 工作副本不存在时先检出
+            if let Some(parent) = workcopy.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let workcopy_str = workcopy.to_string_lossy();
+            self.run(&["checkout", &branch_url, &workcopy_str], None, log).await?.check("svn checkout")?;
+        }
+        Ok(workcopy)
+    }
+
+    // 合并新增的文件在 revert 后会残留为未版本控制文件，一并删除
+    async fn revert_workcopy(&self, workcopy: &Path, log: Option<&TaskLogger>) -> anyhow::Result<()> {
+        for args in [&["cleanup"][..], &["revert", "-R", "."][..], &["cleanup", "--remove-unversioned"][..]] {
+            self.run(args, Some(workcopy), log).await?.check(&format!("svn {}", args.join(" ")))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VersionControl for Svn {
+    // 列出 trunk 与 branches/ 下的所有分支
+    async fn list_branches(&self) -> anyhow::Result<Vec<Branch>> {
+        let root = self.root_url();
+        let branches_url = format!("{}/branches", root);
+        let output = self.run(&["list", "--xml", root, &branches_url], None, None).await?.check("svn list")?;
+        parse_branch_list(&output.stdout)
+    }
+
+    async fn list_commits(&self, branch: &str, limit: u32, before_revision: Option<&str>) -> anyhow::Result<Vec<Commit>> {
+        let range = match before_revision {
+            Some(revision) => {
+                let revision: i64 = revision
+                    .trim_start_matches('r')
+                    .parse()
+                    .with_context(|| format!("invalid revision {}", revision))?;
+                if revision <= 1 {
+                    return Ok(vec![]);
+                }
+                format!("{}:1", revision - 1)
+            }
+            None => "HEAD:1".to_string(),
+        };
+        let url = self.branch_url(branch);
+        let limit = limit.to_string();
+        let output = self
+            .run(&["log", "--xml", "-v", "-r", &range, "-l", &limit, &url], None, None)
+            .await?
+            .check("svn log")?;
+        parse_log(&output.stdout)
+    }
+
+    // 每个单独的修订都必须是来源分支上的提交，修订范围中至少要有一个
+    async fn normalize_revisions(&self, source_branch: &str, revisions: &str) -> anyhow::Result<String> {
+        let set = RevisionSet::parse(revisions).map_err(|e| InvalidRevisions(e.to_string()))?;
+        let url = self.branch_url(source_branch);
+        let head: i64 = self
+            .run(&["info", "--show-item", "revision", &url], None, None)
+            .await?
+            .check("svn info")?
+            .stdout
+            .trim()
+            .parse()
+            .context("invalid svn info output")?;
+
+        let ranges = set.ranges();
+        let (first, last) = (ranges[0].start, ranges[ranges.len() - 1].end);
+        if last > head {
+            return Err(InvalidRevisions(format!("r{} does not exist, the latest revision is r{}", last, head)).into());
+        }
+
+        // 一次查询覆盖整个区间，再逐段检查
+        let range = format!("{}:{}", first, last);
+        let output = self.run(&["log", "--xml", "-q", "-r", &range, &url], None, None).await?.check("svn log")?;
+        let existing: HashSet<i64> = parse_log(&output.stdout)?
+            .iter()
+            .filter_map(|commit| commit.revision.parse().ok())
+            .collect();
+        for range in ranges {
+            if existing.iter().any(|revision| range.contains(*revision)) {
+                continue;
+            }
+            let message = if range.start == range.end {
+                format!("r{} is not a commit on {}", range.start, source_branch)
+            } else {
+                format!("r{} has no commits on {}", range, source_branch)
+            };
+            return Err(InvalidRevisions(message).into());
+        }
+        Ok(set.to_string())
+    }
+
+    // 修订范围中不属于来源分支的修订不会出现在日志中
+    async fn commits(&self, source_branch: &str, revisions: &[String]) -> anyhow::Result<Vec<Commit>> {
+        let url = self.branch_url(source_branch);
+        let ranges: Vec<String> = revisions.iter().map(|range| range.replacen('-', ":", 1)).collect();
+        let mut args = vec!["log", "--xml"];
+        for range in &ranges {
+            args.push("-r");
+            args.push(range);
+        }
+        args.push(&url);
+        let output = self.run(&args, None, None).await?.check("svn log")?;
+        parse_log(&output.stdout)
+    }
+
+    // mergeinfo 只给出修订号，提交信息再从来源分支的日志中取
+    async fn eligible_commits(&self, target_branch: &str, source_branch: &str) -> anyhow::Result<Vec<Commit>> {
+        let eligible = self.mergeinfo("eligible", target_branch, source_branch).await?;
+        let (Some(first), Some(last)) = (eligible.iter().min(), eligible.iter().max()) else {
+            return Ok(vec![]);
+        };
+        let range = format!("{}:{}", last, first);
+        let url = self.branch_url(source_branch);
+        let output = self.run(&["log", "--xml", "-v", "-r", &range, &url], None, None).await?.check("svn log")?;
+        let commits = parse_log(&output.stdout)?
+            .into_iter()
+            .filter(|commit| commit.revision.parse().is_ok_and(|revision| eligible.contains(&revision)))
+            .collect();
+        Ok(commits)
+    }
+
+    async fn ensure_eligible(&self, target_branch: &str, source_branch: &str, revisions: &str) -> anyhow::Result<()> {
+        let set = RevisionSet::parse(revisions).map_err(|e| InvalidRevisions(e.to_string()))?;
+        let mut merged: Vec<i64> = self
+            .mergeinfo("merged", target_branch, source_branch)
+            .await?
+            .into_iter()
+            .filter(|revision| set.contains(*revision))
+            .collect();
+        if merged.is_empty() {
+            return Ok(());
+        }
+        merged.sort_unstable();
+        let merged: Vec<String> = merged.iter().map(|revision| format!("r{}", revision)).collect();
+        Err(InvalidRevisions(format!("{} already merged into {}", merged.join(","), target_branch)).into())
+    }
+
+    fn workcopy_path(&self, branch: &str) -> PathBuf {
+        self.config.workcopy_path(branch)
+    }
+
+    async fn prepare_workcopy(&self, branch: &str, log: &TaskLogger) -> anyhow::Result<PathBuf> {
+        self.prepare(branch, Some(log)).await
+    }
+
+    async fn merge_revisions(&self, workcopy: &Path, source_branch: &str, revisions: &[String], log: &TaskLogger) -> anyhow::Result<()> {
+        let source_url = self.branch_url(source_branch);
+        let changes = revisions.join(",");
+        self.run(&["merge", "-c", &changes, &source_url, "."], Some(workcopy), Some(log))
+            .await?
+            .check("svn merge")?;
+        Ok(())
+    }
+
+    // svn merge --dry-run 只输出将要发生的变化，不修改工作副本
+    async fn preview_merge(&self, branch: &str, source_branch: &str, revisions: &[String]) -> anyhow::Result<MergePreview> {
+        let workcopy = self.prepare(branch, None).await?;
+        let source_url = self.branch_url(source_branch);
+        let changes = revisions.join(",");
+        let output = self
+            .run(&["merge", "--dry-run", "-c", &changes, &source_url, "."], Some(&workcopy), None)
+            .await?
+            .check("svn merge --dry-run")?;
+        let (paths, conflicts) = parse_merge_output(&output.stdout);
+        Ok(MergePreview {
+            revisions: changes,
+            paths,
+            conflicts,
+            ..Default::default()
+        })
+    }
+
+    // 合并过的修订由 svn:mergeinfo 记录
+    async fn commit(&self, workcopy: &Path, message: &str, _revisions: &[String], log: &TaskLogger) -> anyhow::Result<()> {
+        self.run(&["commit", "-m", message, "."], Some(workcopy), Some(log)).await?.check("svn commit")?;
+        Ok(())
+    }
+
+    async fn revert(&self, workcopy: &Path, log: &TaskLogger) -> anyhow::Result<()> {
+        self.revert_workcopy(workcopy, Some(log)).await
+    }
+
+    async fn conflicts(&self, workcopy: &Path) -> anyhow::Result<Vec<Conflict>> {
+        let output = self.run(&["status", "--xml"], Some(workcopy), None).await?.check("svn status")?;
+        parse_conflicts(&output.stdout)
+    }
+
+    async fn has_changes(&self, workcopy: &Path) -> anyhow::Result<bool> {
+        let output = self.run(&["status", "-q"], Some(workcopy), None).await?.check("svn status")?;
+        Ok(!output.stdout.trim().is_empty())
+    }
+}
+
+// 解析 svn list --xml 的输出：第一个 list 为仓库根目录，只取其中的 trunk，
+// 第二个 list 为 branches/，其中的每个目录都是一个分支
+fn parse_branch_list(xml: &str) -> anyhow::Result<Vec<Branch>> {
+    let document = roxmltree::Document::parse(xml).context("invalid svn list output")?;
+    let mut branches = Vec::new();
+    for (index, list) in document.descendants().filter(|node| node.has_tag_name("list")).enumerate() {
+        for entry in list.children().filter(|node| node.has_tag_name("entry")) {
+            if entry.attribute("kind") != Some("dir") {
+                continue;
+            }
+            let name = child_text(entry, "name").unwrap_or_default();
+            if index == 0 && name != "trunk" {
+                continue;
+            }
+            let commit = entry.children().find(|node| node.has_tag_name("commit"));
+            branches.push(Branch {
+                name: name.to_string(),
+                revision: commit.and_then(|commit| commit.attribute("revision")).unwrap_or_default().to_string(),
+                author: commit.and_then(|commit| child_text(commit, "author")).unwrap_or_default().to_string(),
+                date: commit.and_then(|commit| child_text(commit, "date")).unwrap_or_default().to_string(),
+            });
+        }
+    }
+    Ok(branches)
+}
+
+// 解析 svn log --xml -v 的输出
+fn parse_log(xml: &str) -> anyhow::Result<Vec<Commit>> {
+    let document = roxmltree::Document::parse(xml).context("invalid svn log output")?;
+    let commits = document
+        .descendants()
+        .filter(|node| node.has_tag_name("logentry"))
+        .map(|entry| Commit {
+            revision: entry.attribute("revision").unwrap_or_default().to_string(),
+            author: child_text(entry, "author").unwrap_or_default().to_string(),
+            date: child_text(entry, "date").unwrap_or_default().to_string(),
+            message: child_text(entry, "msg").unwrap_or_default().to_string(),
+            changed_paths: entry
+                .descendants()
+                .filter(|node| node.has_tag_name("path"))
+                .map(|path| ChangedPath {
+                    action: path.attribute("action").unwrap_or_default().to_string(),
+                    path: path.text().unwrap_or_default().to_string(),
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(commits)
+}
+
+// 解析 svn status --xml 的输出，一个路径可能同时存在多种冲突
+fn parse_conflicts(xml: &str) -> anyhow::Result<Vec<Conflict>> {
+    let document = roxmltree::Document::parse(xml).context("invalid svn status output")?;
+    let mut conflicts = Vec::new();
+    for entry in document.descendants().filter(|node| node.has_tag_name("entry")) {
+        let path = entry.attribute("path").unwrap_or_default();
+        let Some(status) = entry.children().find(|node| node.has_tag_name("wc-status")) else {
+            continue;
+        };
+        let kinds = [
+            ("text", status.attribute("item") == Some("conflicted")),
+            ("property", status.attribute("props") == Some("conflicted")),
+            ("tree", status.attribute("tree-conflicted") == Some("true")),
+        ];
+        for (kind, conflicted) in kinds {
+            if conflicted {
+                conflicts.push(Conflict {
+                    path: path.to_string(),
+                    kind: kind.to_string(),
+                });
+            }
+        }
+    }
+    Ok(conflicts)
+}
+
+// 解析 svn merge 的输出：每行前四列为内容、属性、锁定、树冲突状态，之后是路径
+// C 表示冲突，其余状态字母表示对应的改动；"--- " 开头的说明行和冲突汇总会被跳过
+fn parse_merge_output(output: &str) -> (Vec<ChangedPath>, Vec<Conflict>) {
+    let mut paths = Vec::new();
+    let mut conflicts = Vec::new();
+    for line in output.lines() {
+        let (Some(code), Some(path)) = (line.get(..4), line.get(5..)) else {
+            continue;
+        };
+        if !line[4..].starts_with(' ') || !code.chars().all(|c| " ADUGECR".contains(c)) || code.trim().is_empty() {
+            continue;
+        }
+        let code: Vec<char> = code.chars().collect();
+        let kinds = [("text", code[0]), ("property", code[1]), ("tree", code[3])];
+        for (kind, status) in kinds {
+            if status == 'C' {
+                conflicts.push(Conflict {
+                    path: path.to_string(),
+                    kind: kind.to_string(),
+                });
+            }
+        }
+        paths.push(ChangedPath {
+            action: code.iter().find(|c| **c != ' ').map(char::to_string).unwrap_or_default(),
+            path: path.to_string(),
+        });
+    }
+    (paths, conflicts)
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children().find(|child| child.has_tag_name(tag)).and_then(|child| child.text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conflict(path: &str, kind: &str) -> Conflict {
+        Conflict {
+            path: path.to_string(),
+            kind: kind.to_string(),
+        }
+    }
+
+    #[test]
+    fn merge_output_lists_paths_and_conflicts() {
+        let output = "\
+--- Merging r1234 into '.':
+U    src/main.c
+A    docs/new.txt
+ U   .
+C    src/conflict.c
+   C src/moved.c
+--- Recording mergeinfo for merge of r1234 into '.':
+ G   .
+Summary of conflicts:
+  Text conflicts: 1
+  Tree conflicts: 1
+";
+        let (paths, conflicts) = parse_merge_output(output);
+        let paths: Vec<(&str, &str)> = paths.iter().map(|path| (path.action.as_str(), path.path.as_str())).collect();
+        assert_eq!(
+            paths,
+            [
+                ("U", "src/main.c"),
+                ("A", "docs/new.txt"),
+                ("U", "."),
+                ("C", "src/conflict.c"),
+                ("C", "src/moved.c"),
+                ("G", "."),
+            ]
+        );
+        assert_eq!(conflicts, [conflict("src/conflict.c", "text"), conflict("src/moved.c", "tree")]);
+    }
+
+    #[test]
+    fn status_lists_every_kind_of_conflict() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<status>
+<target path=".">
+<entry path="src/main.c"><wc-status item="modified" props="none" revision="10"/></entry>
+<entry path="src/both.c"><wc-status item="conflicted" props="conflicted" revision="10"/></entry>
+<entry path="src/moved.c"><wc-status item="missing" props="none" tree-conflicted="true"/></entry>
+</target>
+</status>"#;
+        assert_eq!(
+            parse_conflicts(xml).unwrap(),
+            [conflict("src/both.c", "text"), conflict("src/both.c", "property"), conflict("src/moved.c", "tree")]
+        );
+        assert!(parse_conflicts("not xml").is_err());
+    }
+}